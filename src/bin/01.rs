@@ -56,8 +56,7 @@ pub fn part_two(input: &str) -> Option<u32> {
             let count: u32 = list_2_counts[&entry]
                 .try_into()
                 .expect("Number of entries in file is 10k; count is much smaller than this");
-            let score = entry * count;
-            score
+            entry * count
         })
         .collect::<Vec<_>>()
         .iter()
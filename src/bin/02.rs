@@ -1,24 +1,28 @@
 use itertools::Itertools;
 
-advent_of_code::solution!(2);
+advent_of_code::variants!(2,
+    1 => [naive: part_one],
+    2 => [naive: part_two, fast: part_two_fast];
+    generate = tests::random_reports,
+);
 
-fn all_positive(report_diffs: &Vec<i32>) -> bool {
+fn all_positive(report_diffs: &[i32]) -> bool {
     report_diffs.iter().all(|&x| x > 0)
 }
 
-fn all_negative(report_diffs: &Vec<i32>) -> bool {
+fn all_negative(report_diffs: &[i32]) -> bool {
     report_diffs.iter().all(|&x| x < 0)
 }
 
-fn within_range(report_diffs: &Vec<i32>) -> bool {
+fn within_range(report_diffs: &[i32]) -> bool {
     report_diffs.iter().all(|&x| x.abs() >= 1 && x.abs() <= 3)
 }
 
-fn is_safe(report_diffs: &Vec<i32>) -> bool {
-    (all_positive(&report_diffs) || all_negative(&report_diffs)) && within_range(&report_diffs)
+fn is_safe(report_diffs: &[i32]) -> bool {
+    (all_positive(report_diffs) || all_negative(report_diffs)) && within_range(report_diffs)
 }
 
-fn report_diffs(report: &Vec<i32>) -> Vec<i32> {
+fn report_diffs(report: &[i32]) -> Vec<i32> {
     let mut report_diffs = vec![];
     for (a, b) in report.iter().tuple_windows() {
         report_diffs.push(a - b)
@@ -26,17 +30,53 @@ fn report_diffs(report: &Vec<i32>) -> Vec<i32> {
     report_diffs
 }
 
-fn shameless_copies(report: &Vec<i32>) -> Vec<Vec<i32>> {
+fn shameless_copies(report: &[i32]) -> Vec<Vec<i32>> {
     let mut shameless_copies = vec![];
     for (i, _) in report.iter().enumerate() {
-        let mut diff_copy = report.clone();
+        let mut diff_copy = report.to_owned();
         diff_copy.remove(i);
         shameless_copies.push(diff_copy);
     }
     shameless_copies
 }
 
-fn safe_reports(input: &str, dampener: bool) -> u32 {
+fn is_safe_with_copies(report: &[i32]) -> bool {
+    shameless_copies(report)
+        .iter()
+        .any(|report| is_safe(&report_diffs(report)))
+}
+
+fn is_safe_step(a: i32, b: i32, increasing: bool) -> bool {
+    let diff = if increasing { b - a } else { a - b };
+    (1..=3).contains(&diff)
+}
+
+fn is_safe_without(report: &[i32], skipped: usize, increasing: bool) -> bool {
+    report
+        .iter()
+        .enumerate()
+        .filter(|&(i, _)| i != skipped)
+        .tuple_windows()
+        .all(|((_, &a), (_, &b))| is_safe_step(a, b, increasing))
+}
+
+/// The first unsafe step must involve the removed level, so only its two ends are worth removing.
+fn is_safe_with_single_removal(report: &[i32]) -> bool {
+    [true, false].into_iter().any(|increasing| {
+        let first_unsafe = report
+            .iter()
+            .tuple_windows()
+            .position(|(&a, &b)| !is_safe_step(a, b, increasing));
+        match first_unsafe {
+            None => true,
+            Some(i) => {
+                is_safe_without(report, i, increasing) || is_safe_without(report, i + 1, increasing)
+            }
+        }
+    })
+}
+
+fn safe_reports(input: &str, dampener: Option<fn(&[i32]) -> bool>) -> u32 {
     let reports = input.lines().map(|line| {
         line.split_whitespace()
             .map(|level| level.parse::<i32>().expect("All levels fit into i32"))
@@ -45,13 +85,9 @@ fn safe_reports(input: &str, dampener: bool) -> u32 {
     let safety = reports.map(|report| {
         let report_diff = report_diffs(&report);
         let safe_report = is_safe(&report_diff);
-        if dampener && !safe_report {
-            let report_copies = shameless_copies(&report);
-            report_copies
-                .iter()
-                .any(|report| is_safe(&report_diffs(&report)))
-        } else {
-            safe_report
+        match dampener {
+            Some(dampener) if !safe_report => dampener(&report),
+            _ => safe_report,
         }
     });
     let result: i32 = safety.map(|x| x as i32).sum();
@@ -60,16 +96,35 @@ fn safe_reports(input: &str, dampener: bool) -> u32 {
 }
 
 pub fn part_one(input: &str) -> Option<u32> {
-    Some(safe_reports(input, false))
+    Some(safe_reports(input, None))
 }
 
 pub fn part_two(input: &str) -> Option<u32> {
-    Some(safe_reports(input, true))
+    Some(safe_reports(input, Some(is_safe_with_copies)))
+}
+
+pub fn part_two_fast(input: &str) -> Option<u32> {
+    Some(safe_reports(input, Some(is_safe_with_single_removal)))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::template::variants::Rng;
+
+    pub fn random_reports(rng: &mut Rng) -> String {
+        (0..20)
+            .map(|_| {
+                let mut level = rng.range(1..20) as i32;
+                (0..rng.range(2..9))
+                    .map(|_| {
+                        level += rng.range(0..9) as i32 - 4;
+                        level.to_string()
+                    })
+                    .join(" ")
+            })
+            .join("\n")
+    }
 
     #[test]
     fn test_part_one() {
@@ -82,4 +137,10 @@ mod tests {
         let result = part_two(&advent_of_code::template::read_file("examples", DAY));
         assert_eq!(result, Some(4));
     }
+
+    #[test]
+    fn test_part_two_fast() {
+        let result = part_two_fast(&advent_of_code::template::read_file("examples", DAY));
+        assert_eq!(result, Some(4));
+    }
 }
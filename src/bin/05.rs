@@ -1,16 +1,49 @@
 use itertools::Itertools;
-use std::collections::{HashMap, HashSet};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
+use std::fmt;
 
-advent_of_code::solution!(5);
+advent_of_code::solution!(5; explain = explain);
 
 struct ManualSolver {
-    rules: HashMap<(u32, u32), u32>,
+    /// `(before, after)` for every rule.
+    rules: HashSet<(u32, u32)>,
     updates: Vec<Vec<u32>>,
 }
 
-struct ManualResults {
-    ordered_page_sum: u32,
-    unordered_page_sum: u32,
+/// Pages whose rules each require the page to come before the next one, wrapping around.
+#[derive(Debug, PartialEq)]
+struct RuleCycle(Vec<u32>);
+
+impl fmt::Display for RuleCycle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let pages = self.0.iter().chain(self.0.first()).join(" -> ");
+        write!(f, "Rules form a cycle: {pages}")
+    }
+}
+
+/// Why an update is out of order, and the order that fixes it.
+struct Explanation {
+    update: Vec<u32>,
+    /// `(before, after)` for each rule the update breaks by putting `after` first.
+    violations: Vec<(u32, u32)>,
+    reordered: Result<Vec<u32>, RuleCycle>,
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Update {}", self.update.iter().join(","))?;
+        let violations = self
+            .violations
+            .iter()
+            .map(|(before, after)| format!("{before}|{after}"))
+            .join(", ");
+        writeln!(f, "  breaks {violations}")?;
+        match &self.reordered {
+            Ok(reordered) => write!(f, "  corrected to {}", reordered.iter().join(",")),
+            Err(cycle) => write!(f, "  cannot be corrected. {cycle}"),
+        }
+    }
 }
 
 impl ManualSolver {
@@ -20,7 +53,7 @@ impl ManualSolver {
         let updates_input = input_iter.next().expect("Updates should be present");
         assert_eq!(None, input_iter.next());
 
-        let mut rules = HashMap::new();
+        let mut rules = HashSet::new();
         for rule in rules_input.lines() {
             let mut rule_iter = rule.split("|");
             let first_num: u32 = rule_iter
@@ -33,8 +66,7 @@ impl ManualSolver {
                 .expect("Second number present")
                 .parse()
                 .expect("Fits into u32");
-            rules.insert((first_num, second_num), first_num);
-            rules.insert((second_num, first_num), first_num);
+            rules.insert((first_num, second_num));
         }
 
         let updates: Vec<Vec<u32>> = updates_input
@@ -50,107 +82,130 @@ impl ManualSolver {
         Self { rules, updates }
     }
 
-    fn get_page_pairs(&self, update: &Vec<u32>) -> HashMap<u32, HashSet<Vec<u32>>> {
-        let mut page_pairs = HashMap::new();
-        for page_pair in update.iter().combinations(2) {
-            let page_1 = page_pairs.entry(*page_pair[0]).or_insert_with(HashSet::new);
-            page_1.insert(page_pair.iter().map(|&&x| x).collect());
-            let page_2 = page_pairs.entry(*page_pair[1]).or_insert_with(HashSet::new);
-            page_2.insert(page_pair.iter().map(|&&x| x).collect());
-        }
-        page_pairs
-    }
-
-    fn remove_page_from_page_pairs(
-        &self,
-        page: u32,
-        page_pairs: &mut HashMap<u32, HashSet<Vec<u32>>>,
-    ) {
-        for page_combos in page_pairs.values_mut() {
-            for page_combo in page_combos.clone() {
-                if page_combo.contains(&page) {
-                    page_combos.remove(&page_combo);
+    /// Whether no rule requires a later page to come before an earlier one. Pages without a
+    /// rule between them can be in either order.
+    fn is_ordered(&self, update: &[u32]) -> bool {
+        self.violations(update).next().is_none()
+    }
+
+    /// The rules broken by the update, as `(before, after)`.
+    fn violations<'a>(&'a self, update: &'a [u32]) -> impl Iterator<Item = (u32, u32)> + 'a {
+        update
+            .iter()
+            .tuple_combinations()
+            .map(|(&earlier, &later)| (later, earlier))
+            .filter(|rule| self.rules.contains(rule))
+    }
+
+    fn explain(&self) -> Vec<Explanation> {
+        self.updates
+            .iter()
+            .filter(|update| !self.is_ordered(update))
+            .map(|update| Explanation {
+                update: update.clone(),
+                violations: self.violations(update).collect(),
+                reordered: self.reorder_update(update),
+            })
+            .collect()
+    }
+
+    /// Kahn's algorithm over the rules between this update's pages. Whenever several pages are
+    /// free to go next, the one earliest in the update goes first.
+    fn reorder_update(&self, update: &[u32]) -> Result<Vec<u32>, RuleCycle> {
+        let mut successors = vec![vec![]; update.len()];
+        let mut in_degree = vec![0; update.len()];
+        for (i, j) in (0..update.len()).tuple_combinations() {
+            for (before, after) in [(i, j), (j, i)] {
+                if self.rules.contains(&(update[before], update[after])) {
+                    successors[before].push(after);
+                    in_degree[after] += 1;
                 }
             }
         }
-    }
 
-    fn reorder_update(
-        &self,
-        update: &Vec<u32>,
-        page_pairs: &mut HashMap<u32, HashSet<Vec<u32>>>,
-    ) -> Vec<u32> {
-        let mut sorted_update = vec![];
-        while sorted_update.len() != update.len() {
-            let mut found_earliest = false;
-            let mut earliest_page = None;
-            for (page, pairs) in page_pairs.clone().iter() {
-                if !(page_pairs.len() == 1
-                    || pairs
-                        .iter()
-                        .all(|page_combo| *page == self.rules[&(page_combo[0], page_combo[1])]))
-                {
-                    continue;
+        let mut ready: BinaryHeap<_> = (0..update.len())
+            .filter(|&i| in_degree[i] == 0)
+            .map(Reverse)
+            .collect();
+        let mut sorted_update = Vec::with_capacity(update.len());
+        while let Some(Reverse(i)) = ready.pop() {
+            sorted_update.push(update[i]);
+            for &j in &successors[i] {
+                in_degree[j] -= 1;
+                if in_degree[j] == 0 {
+                    ready.push(Reverse(j));
                 }
-                found_earliest = true;
-                earliest_page = Some(*page);
-                sorted_update.push(*page);
-                page_pairs.remove(page);
-                break;
-            }
-            let earliest_page = earliest_page.expect(
-                "Page pairs must be populated whilst sorted_update is not the same length as update",
-            );
-            if found_earliest {
-                self.remove_page_from_page_pairs(earliest_page, page_pairs);
-                continue;
             }
         }
-        sorted_update
-    }
 
-    fn solve_unordered(&self, unordered_updates: Vec<&Vec<u32>>) -> u32 {
-        let mut unordered_page_sum = 0;
-        for update in unordered_updates.into_iter() {
-            let mut page_pairs = self.get_page_pairs(update);
-            let sorted_update = self.reorder_update(update, &mut page_pairs);
-            unordered_page_sum += sorted_update[sorted_update.len() / 2];
+        if sorted_update.len() == update.len() {
+            Ok(sorted_update)
+        } else {
+            Err(self.find_cycle(update, &in_degree))
         }
-        unordered_page_sum
-    }
-
-    fn solve(&self) -> ManualResults {
-        let mut unordered_updates = vec![];
-        let mut ordered_page_sum = 0;
-        'outer: for update in self.updates.iter() {
-            for page_pair in update.iter().combinations(2) {
-                let left_page = self
-                    .rules
-                    .get(&(*page_pair[0], *page_pair[1]))
-                    .expect("Page pair will be present in rules");
-                if *page_pair[0] != *left_page {
-                    unordered_updates.push(update);
-                    continue 'outer;
-                }
+    }
+
+    /// Every page left over by Kahn's algorithm has a leftover page that must come before it,
+    /// so walking backwards through those pages must eventually repeat one.
+    fn find_cycle(&self, update: &[u32], in_degree: &[usize]) -> RuleCycle {
+        let leftover: Vec<usize> = (0..update.len()).filter(|&i| in_degree[i] > 0).collect();
+        let mut walk = vec![leftover[0]];
+        loop {
+            let page = update[*walk.last().expect("walk is never empty")];
+            let predecessor = *leftover
+                .iter()
+                .find(|&&i| self.rules.contains(&(update[i], page)))
+                .expect("Leftover pages have a leftover predecessor");
+            if let Some(start) = walk.iter().position(|&i| i == predecessor) {
+                let mut cycle: Vec<usize> = walk[start..].iter().rev().copied().collect();
+                let first = cycle.iter().position_min().expect("cycle is never empty");
+                cycle.rotate_left(first);
+                return RuleCycle(cycle.into_iter().map(|i| update[i]).collect());
             }
-            ordered_page_sum += update[update.len() / 2];
+            walk.push(predecessor);
         }
+    }
 
-        let unordered_page_sum = self.solve_unordered(unordered_updates);
+    fn ordered_page_sum(&self) -> u32 {
+        self.updates
+            .iter()
+            .filter(|update| self.is_ordered(update))
+            .map(|update| update[update.len() / 2])
+            .sum()
+    }
 
-        ManualResults {
-            ordered_page_sum,
-            unordered_page_sum,
+    fn unordered_page_sum(&self) -> Result<u32, RuleCycle> {
+        let mut unordered_page_sum = 0;
+        for update in self
+            .updates
+            .iter()
+            .filter(|update| !self.is_ordered(update))
+        {
+            let sorted_update = self.reorder_update(update)?;
+            unordered_page_sum += sorted_update[sorted_update.len() / 2];
         }
+        Ok(unordered_page_sum)
+    }
+}
+
+fn explain(input: &str) {
+    for explanation in ManualSolver::new(input).explain() {
+        println!("{explanation}");
     }
 }
 
 pub fn part_one(input: &str) -> Option<u32> {
-    Some(ManualSolver::new(input).solve().ordered_page_sum)
+    Some(ManualSolver::new(input).ordered_page_sum())
 }
 
 pub fn part_two(input: &str) -> Option<u32> {
-    Some(ManualSolver::new(input).solve().unordered_page_sum)
+    match ManualSolver::new(input).unordered_page_sum() {
+        Ok(sum) => Some(sum),
+        Err(cycle) => {
+            eprintln!("{cycle}");
+            None
+        }
+    }
 }
 
 #[cfg(test)]
@@ -180,4 +235,45 @@ mod tests {
         ));
         assert_eq!(result, Some(123));
     }
+
+    #[test]
+    fn test_reorder_update_without_every_rule() {
+        let solver = ManualSolver::new("1|2\n\n3,2,1\n");
+        assert!(!solver.is_ordered(&[3, 2, 1]));
+        assert_eq!(solver.reorder_update(&[3, 2, 1]), Ok(vec![3, 1, 2]));
+        assert_eq!(part_two("1|2\n\n3,2,1\n"), Some(1));
+    }
+
+    #[test]
+    fn test_reorder_update_reports_cycle() {
+        let solver = ManualSolver::new("1|2\n2|3\n3|1\n3|4\n\n4,2,3,1\n");
+        let cycle = solver.reorder_update(&[4, 2, 3, 1]).unwrap_err();
+        assert_eq!(cycle, RuleCycle(vec![2, 3, 1]));
+        assert_eq!(cycle.to_string(), "Rules form a cycle: 2 -> 3 -> 1 -> 2");
+    }
+
+    #[test]
+    fn test_explain() {
+        let solver = ManualSolver::new(&advent_of_code::template::read_file_part(
+            "examples", DAY, 1,
+        ));
+        let explanations = solver.explain();
+        let violations: Vec<_> = explanations
+            .iter()
+            .map(|explanation| explanation.violations.clone())
+            .collect();
+        assert_eq!(
+            violations,
+            [
+                vec![(97, 75)],
+                vec![(29, 13)],
+                vec![(75, 13), (29, 13), (47, 13), (47, 29)],
+            ]
+        );
+        assert_eq!(explanations[2].reordered, Ok(vec![97, 75, 47, 29, 13]));
+        assert_eq!(
+            explanations[0].to_string(),
+            "Update 75,97,47,61,53\n  breaks 97|75\n  corrected to 97,75,47,61,53"
+        );
+    }
 }
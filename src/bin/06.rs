@@ -1,7 +1,13 @@
+use advent_of_code::cycle::{self, Cycle};
+use advent_of_code::grid::{Cell, Grid, Render};
+use advent_of_code::image::{Palette, Rgb};
+use advent_of_code::simulation::{self, Simulation};
+use advent_of_code::template::ANSI_BOLD;
 use std::collections::{HashMap, HashSet};
+use std::iter;
 use std::ops::Add;
 
-advent_of_code::solution!(6);
+advent_of_code::solution!(6; visualize = visualize);
 
 #[derive(PartialEq)]
 enum Space {
@@ -24,6 +30,10 @@ enum Direction {
 }
 
 impl Direction {
+    fn index(&self) -> usize {
+        *self as usize
+    }
+
     fn turn_right(&self) -> Direction {
         match self {
             Direction::Up => Direction::Right,
@@ -59,17 +69,6 @@ struct ParsedMap {
     map: HashMap<Point, Space>,
 }
 
-#[derive(PartialEq)]
-enum PathEnd {
-    Exit,
-    Cycle,
-}
-
-struct PathOutput {
-    end_type: PathEnd,
-    visited: HashSet<Point>,
-}
-
 fn parsed_map(input: &str) -> ParsedMap {
     let mut start = None;
     let mut map = HashMap::new();
@@ -94,75 +93,457 @@ fn parsed_map(input: &str) -> ParsedMap {
     ParsedMap { start, map }
 }
 
-fn trace_path(
-    mut pos: Point,
+/// The guard's next position and direction, or `None` once the guard leaves the map.
+fn next_state(
     map: &HashMap<Point, Space>,
     added_obstruction: Option<Point>,
-) -> PathOutput {
-    let mut next_pos;
-    let mut dir = Direction::Up;
-    let mut visited = HashSet::new();
-    let mut visited_orientation = HashSet::new();
-
-    loop {
-        if visited_orientation.contains(&(pos, dir)) {
-            return PathOutput {
-                end_type: PathEnd::Cycle,
-                visited,
-            };
+    (pos, dir): (Point, Direction),
+) -> Option<(Point, Direction)> {
+    let next_pos = pos + dir.point_offset();
+    match map.get(&next_pos) {
+        None => None,
+        Some(Space::Empty) if Some(next_pos) != added_obstruction => Some((next_pos, dir)),
+        _ => {
+            // Space::Obstruction or Space::Empty but not added obstruction
+            Some((pos, dir.turn_right()))
+        }
+    }
+}
+
+/// The guard's walk, one state per step or turn, in order.
+struct Path {
+    steps: Vec<(Point, Direction)>,
+    /// Where the steps start repeating, or `None` if the guard leaves the map.
+    cycle: Option<Cycle>,
+}
+
+impl Path {
+    fn visited(&self) -> HashSet<Point> {
+        self.steps.iter().map(|&(pos, _)| pos).collect()
+    }
+
+    /// The states the guard repeats forever, empty if it leaves the map.
+    fn cycle(&self) -> &[(Point, Direction)] {
+        match &self.cycle {
+            Some(cycle) => &self.steps[cycle.offset..],
+            None => &[],
+        }
+    }
+}
+
+fn trace_path(start: Point, map: &HashMap<Point, Space>, added_obstruction: Option<Point>) -> Path {
+    let mut steps = vec![];
+
+    let states = iter::successors(Some((start, Direction::Up)), |&state| {
+        next_state(map, added_obstruction, state)
+    })
+    .inspect(|&state| steps.push(state));
+
+    let cycle = cycle::find_cycle(states);
+    if let Some(cycle) = &cycle {
+        // the last state is the first repeat.
+        steps.truncate(cycle.offset + cycle.period);
+    }
+    Path { steps, cycle }
+}
+
+/// An obstruction that traps the guard, with the states of the loop it then walks.
+struct LoopObstruction {
+    position: Point,
+    cycle: Vec<(Point, Direction)>,
+}
+
+/// Every position where a new obstruction makes the guard loop, in the order the guard reaches
+/// them on its original path.
+fn loop_obstructions(input: &str) -> Vec<LoopObstruction> {
+    let ParsedMap { start, map } = parsed_map(input);
+    let lab = Lab::new(input);
+    lab.loop_obstructions()
+        .into_iter()
+        .map(|cell| {
+            let position = lab.point(cell);
+            let path = trace_path(start, &map, Some(position));
+            LoopObstruction {
+                position,
+                cycle: path.cycle().to_vec(),
+            }
+        })
+        .collect()
+}
+
+/// Draws the map with `steps` in the puzzle's notation: `|` and `-` where the guard walked, `+`
+/// where it turned or crossed its own path, and `O` at each of `obstructions`.
+fn render_path<'a>(
+    map: &'a HashMap<Point, Space>,
+    steps: &[(Point, Direction)],
+    obstructions: impl IntoIterator<Item = Point>,
+) -> Render<'a> {
+    let mut markers = HashMap::new();
+    for &(pos, dir) in steps {
+        let marker = match dir {
+            Direction::Up | Direction::Down => '|',
+            Direction::Right | Direction::Left => '-',
+        };
+        markers
+            .entry(pos)
+            .and_modify(|existing| {
+                if *existing != marker {
+                    *existing = '+';
+                }
+            })
+            .or_insert(marker);
+    }
+    let (width, height) = map.keys().fold((0, 0), |(w, h), point| {
+        (w.max(point.x as usize + 1), h.max(point.y as usize + 1))
+    });
+    Render::new(width, height, move |(x, y)| {
+        let point = Point {
+            x: x as i32,
+            y: y as i32,
+        };
+        match (map.get(&point), markers.get(&point)) {
+            (Some(Space::Obstruction), _) => '#',
+            (_, Some(&marker)) => marker,
+            _ => '.',
         }
-        visited.insert(pos);
-        visited_orientation.insert((pos, dir));
+    })
+    .overlay(
+        obstructions
+            .into_iter()
+            .map(|point| (point.x as usize, point.y as usize)),
+        'O',
+    )
+}
+
+/// Marker in the jump tables for a guard that walks off the map.
+const EXIT: u32 = u32::MAX;
 
-        next_pos = pos + dir.point_offset();
+/// Dense copy of the map for part two, with cells indexed `y * width + x`.
+struct Lab {
+    width: usize,
+    height: usize,
+    start: usize,
+    obstructions: Vec<bool>,
+    /// Per direction, the cell a guard standing on each cell stops at before the next
+    /// obstruction, or `EXIT`.
+    stops: [Vec<u32>; 4],
+}
+
+impl Lab {
+    fn new(input: &str) -> Self {
+        let grid = Grid::parse(input, |space| match space {
+            '.' | '^' => false,
+            '#' => true,
+            _ => panic!("Unexpected value"),
+        });
+        let width = grid.width();
+        let height = grid.height();
+        let start = input
+            .lines()
+            .flat_map(str::chars)
+            .position(|space| space == '^')
+            .expect("^ must be present in input");
+        let obstructions: Vec<bool> = grid.iter().map(|(_, &blocked)| blocked).collect();
 
-        match map.get(&next_pos) {
-            None => {
-                return PathOutput {
-                    end_type: PathEnd::Exit,
-                    visited,
+        let mut stops = [(); 4].map(|_| vec![EXIT; width * height]);
+        let index = |x: usize, y: usize| y * width + x;
+        // sweep each row and column against the walking direction, remembering the cell
+        // in front of the last obstruction seen.
+        for x in 0..width {
+            let mut stop = EXIT;
+            for y in 0..height {
+                if obstructions[index(x, y)] {
+                    stop = if y + 1 < height {
+                        index(x, y + 1) as u32
+                    } else {
+                        EXIT
+                    };
+                } else {
+                    stops[Direction::Up.index()][index(x, y)] = stop;
+                }
+            }
+            let mut stop = EXIT;
+            for y in (0..height).rev() {
+                if obstructions[index(x, y)] {
+                    stop = if y > 0 { index(x, y - 1) as u32 } else { EXIT };
+                } else {
+                    stops[Direction::Down.index()][index(x, y)] = stop;
                 }
             }
-            Some(Space::Empty) if Some(next_pos) != added_obstruction => {
-                pos = next_pos;
+        }
+        for y in 0..height {
+            let mut stop = EXIT;
+            for x in 0..width {
+                if obstructions[index(x, y)] {
+                    stop = if x + 1 < width {
+                        index(x + 1, y) as u32
+                    } else {
+                        EXIT
+                    };
+                } else {
+                    stops[Direction::Left.index()][index(x, y)] = stop;
+                }
+            }
+            let mut stop = EXIT;
+            for x in (0..width).rev() {
+                if obstructions[index(x, y)] {
+                    stop = if x > 0 { index(x - 1, y) as u32 } else { EXIT };
+                } else {
+                    stops[Direction::Right.index()][index(x, y)] = stop;
+                }
             }
-            _ => {
-                // Space::Obstruction or Space::Empty but not added obstruction
+        }
+
+        Lab {
+            width,
+            height,
+            start,
+            obstructions,
+            stops,
+        }
+    }
+
+    fn point(&self, cell: usize) -> Point {
+        Point {
+            x: (cell % self.width) as i32,
+            y: (cell / self.width) as i32,
+        }
+    }
+
+    /// The cells where an added obstruction makes the guard loop, in path order.
+    fn loop_obstructions(&self) -> Vec<usize> {
+        let mut turns = BitSet::new(self.width * self.height * 4);
+        let mut path_turns = BitSet::new(self.width * self.height * 4);
+        let mut tried = vec![false; self.width * self.height];
+        tried[self.start] = true;
+
+        // an obstruction only changes the path from the first time the guard would reach it,
+        // so each probe starts from the guard's state just before that cell.
+        let mut looped_obstructions = vec![];
+        let (mut cell, mut dir) = (self.start, Direction::Up);
+        while let Some(next) = self.step(cell, dir) {
+            if self.obstructions[next] {
+                if !path_turns.insert(cell * 4 + dir.index()) {
+                    // the guard never leaves, so every cell it can reach has been tried.
+                    break;
+                }
                 dir = dir.turn_right();
+                continue;
+            }
+            if !tried[next] {
+                tried[next] = true;
+                if self.loops((cell, dir), next, &mut turns) {
+                    looped_obstructions.push(next);
+                }
+                turns.clear();
             }
+            cell = next;
+        }
+        looped_obstructions
+    }
+
+    /// The cell one step from `cell` in `dir`, if it is on the map.
+    fn step(&self, cell: usize, dir: Direction) -> Option<usize> {
+        let (x, y) = (cell % self.width, cell / self.width);
+        match dir {
+            Direction::Up => (y > 0).then(|| cell - self.width),
+            Direction::Right => (x + 1 < self.width).then(|| cell + 1),
+            Direction::Down => (y + 1 < self.height).then(|| cell + self.width),
+            Direction::Left => (x > 0).then(|| cell - 1),
+        }
+    }
+
+    /// Distance along `dir` and offset across it, so cells ahead on the same line share
+    /// `across` and have a larger `along`.
+    fn axes(&self, cell: usize, dir: Direction) -> (isize, usize) {
+        let (x, y) = (cell % self.width, cell / self.width);
+        match dir {
+            Direction::Up => (-(y as isize), x),
+            Direction::Right => (x as isize, y),
+            Direction::Down => (y as isize, x),
+            Direction::Left => (-(x as isize), y),
+        }
+    }
+
+    /// Where the guard stops walking from `cell` in `dir` with one extra obstruction.
+    fn jump(&self, cell: usize, dir: Direction, added_obstruction: usize) -> u32 {
+        let stop = self.stops[dir.index()][cell];
+        let (along, across) = self.axes(cell, dir);
+        let (added_along, added_across) = self.axes(added_obstruction, dir);
+        let blocked = added_across == across
+            && added_along > along
+            && (stop == EXIT || added_along <= self.axes(stop as usize, dir).0);
+        if blocked {
+            let back = dir.turn_right().turn_right();
+            self.step(added_obstruction, back)
+                .expect("cell before the added obstruction is on the map") as u32
+        } else {
+            stop
+        }
+    }
+
+    /// Whether the guard, standing on `cell` facing `dir`, loops once `added_obstruction`
+    /// is placed. Only turns are recorded, in `turns`, which must be empty on entry.
+    fn loops(
+        &self,
+        (mut cell, mut dir): (usize, Direction),
+        added_obstruction: usize,
+        turns: &mut BitSet,
+    ) -> bool {
+        loop {
+            let stop = self.jump(cell, dir, added_obstruction);
+            if stop == EXIT {
+                return false;
+            }
+            cell = stop as usize;
+            if !turns.insert(cell * 4 + dir.index()) {
+                return true;
+            }
+            dir = dir.turn_right();
         }
     }
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let ParsedMap { start, map } = parsed_map(input);
-    let path = trace_path(start, &map, None);
-    Some(
-        path.visited
-            .len()
-            .try_into()
-            .expect("Path length fits into u32"),
-    )
+/// Fixed-size set of small integers, cleared by only resetting the words that were used.
+struct BitSet {
+    words: Vec<u64>,
+    touched: Vec<usize>,
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    let ParsedMap { start, map } = parsed_map(input);
-    let orig_path = trace_path(start, &map, None);
+impl BitSet {
+    fn new(size: usize) -> Self {
+        BitSet {
+            words: vec![0; size.div_ceil(64)],
+            touched: Vec::new(),
+        }
+    }
 
-    let mut looped_obstructions = 0;
-    for point in orig_path.visited {
-        let space = map
-            .get(&point)
-            .expect("point guaranteed to be present in map");
-        if point == start || *space == Space::Obstruction {
-            continue;
+    /// Adds `value`, returning whether it was newly inserted.
+    fn insert(&mut self, value: usize) -> bool {
+        let (word, bit) = (value / 64, 1 << (value % 64));
+        if self.words[word] & bit != 0 {
+            return false;
         }
-        let path = trace_path(start, &map, Some(point));
-        if path.end_type == PathEnd::Cycle {
-            looped_obstructions += 1;
+        if self.words[word] == 0 {
+            self.touched.push(word);
+        }
+        self.words[word] |= bit;
+        true
+    }
+
+    fn clear(&mut self) {
+        for word in self.touched.drain(..) {
+            self.words[word] = 0;
+        }
+    }
+}
+
+/// The guard walking the map one step at a time, for `--visualize`.
+struct Patrol {
+    map: HashMap<Point, Space>,
+    path: Vec<(Point, Direction)>,
+    done: bool,
+    /// Drawn once the guard has walked past them.
+    loop_obstructions: HashSet<Point>,
+}
+
+impl Patrol {
+    fn new(input: &str, loops: &[LoopObstruction]) -> Self {
+        let ParsedMap { start, map } = parsed_map(input);
+        let loop_obstructions = loops
+            .iter()
+            .map(|obstruction| obstruction.position)
+            .collect();
+        Self {
+            map,
+            path: vec![(start, Direction::Up)],
+            done: false,
+            loop_obstructions,
         }
     }
-    Some(looped_obstructions)
+}
+
+impl Simulation for Patrol {
+    fn step(&mut self) {
+        let guard = *self.path.last().expect("path starts at the guard");
+        match next_state(&self.map, None, guard) {
+            Some(state) => self.path.push(state),
+            None => self.done = true,
+        }
+    }
+
+    fn render(&self) -> String {
+        let visited: HashSet<_> = self.path.iter().map(|&(pos, _)| pos).collect();
+        let passed = self
+            .loop_obstructions
+            .iter()
+            .copied()
+            .filter(|pos| visited.contains(pos));
+        let mut render = render_path(&self.map, &self.path, passed);
+        if !self.done {
+            let (pos, dir) = *self.path.last().expect("path starts at the guard");
+            let glyph = match dir {
+                Direction::Up => '^',
+                Direction::Right => '>',
+                Direction::Down => 'v',
+                Direction::Left => '<',
+            };
+            render = render.overlay(
+                [(pos.x as usize, pos.y as usize)],
+                Cell::styled(glyph, ANSI_BOLD),
+            );
+        }
+        render.coloured().to_string()
+    }
+
+    fn is_done(&self) -> bool {
+        self.done
+    }
+}
+
+fn visualize(input: &str) {
+    let guard = Rgb(255, 64, 64);
+    let palette = Palette::default()
+        .with('#', Rgb(128, 128, 128))
+        .with('|', Rgb(64, 96, 255))
+        .with('-', Rgb(64, 96, 255))
+        .with('+', Rgb(64, 96, 255))
+        .with('O', Rgb(255, 204, 0))
+        .with('^', guard)
+        .with('>', guard)
+        .with('v', guard)
+        .with('<', guard);
+    let loops = loop_obstructions(input);
+    simulation::visualize(&mut Patrol::new(input, &loops), &palette);
+
+    let ParsedMap { map, .. } = parsed_map(input);
+    for (i, obstruction) in loops.iter().enumerate() {
+        let Point { x, y } = obstruction.position;
+        println!(
+            "\nLoop {}: obstruction at ({x}, {y}), cycle of {} states",
+            i + 1,
+            obstruction.cycle.len()
+        );
+        println!(
+            "{}",
+            render_path(&map, &obstruction.cycle, [obstruction.position])
+        );
+    }
+}
+
+pub fn part_one(input: &str) -> Option<u32> {
+    let ParsedMap { start, map } = parsed_map(input);
+    let visited = trace_path(start, &map, None).visited();
+    Some(visited.len().try_into().expect("Path length fits into u32"))
+}
+
+pub fn part_two(input: &str) -> Option<u32> {
+    let looped_obstructions = Lab::new(input).loop_obstructions().len();
+    Some(
+        looped_obstructions
+            .try_into()
+            .expect("Obstruction count fits into u32"),
+    )
 }
 
 #[cfg(test)]
@@ -180,4 +561,98 @@ mod tests {
         let result = part_two(&advent_of_code::template::read_file("examples", DAY));
         assert_eq!(result, Some(6));
     }
+
+    #[test]
+    fn test_jump_to_added_obstruction() {
+        let lab = Lab::new(&advent_of_code::template::read_file("examples", DAY));
+        let cell = |x: usize, y: usize| y * lab.width + x;
+        // Where the guard at its start stops when moving in `dir` with an obstruction added at
+        // `(x, y)`, or `None` if it leaves the map.
+        let jump = |dir, (x, y)| {
+            let stop = lab.jump(lab.start, dir, cell(x, y));
+            (stop != EXIT).then(|| {
+                let Point { x, y } = lab.point(stop as usize);
+                (x, y)
+            })
+        };
+        // The guard starts at (4, 6), below an obstruction at (4, 0) and right of one at (1, 6).
+        assert!(lab.point(lab.start) == Point { x: 4, y: 6 });
+        assert_eq!(jump(Direction::Up, (0, 4)), Some((4, 1)));
+        assert_eq!(jump(Direction::Up, (4, 3)), Some((4, 4)));
+        assert_eq!(jump(Direction::Left, (4, 3)), Some((2, 6)));
+        assert_eq!(jump(Direction::Right, (4, 3)), None);
+        assert_eq!(jump(Direction::Right, (9, 6)), Some((8, 6)));
+    }
+
+    #[test]
+    fn test_loop_obstructions() {
+        let loops = loop_obstructions(&advent_of_code::template::read_file("examples", DAY));
+        let mut positions: Vec<_> = loops
+            .iter()
+            .map(|obstruction| (obstruction.position.x, obstruction.position.y))
+            .collect();
+        positions.sort_by_key(|&(x, y)| (y, x));
+        assert_eq!(positions, [(3, 6), (6, 7), (7, 7), (1, 8), (3, 8), (7, 9)]);
+        assert!(loops
+            .iter()
+            .all(|obstruction| !obstruction.cycle.is_empty()));
+    }
+
+    #[test]
+    fn test_render_loop() {
+        let input = advent_of_code::template::read_file("examples", DAY);
+        let ParsedMap { start, map } = parsed_map(&input);
+        let obstruction = Point { x: 3, y: 6 };
+        let path = trace_path(start, &map, Some(obstruction));
+        assert_eq!(path.cycle().len(), 22);
+        let render = render_path(&map, &path.steps, [obstruction]).overlay([(4, 6)], '^');
+        assert_eq!(
+            render.to_string(),
+            concat!(
+                "....#.....\n",
+                "....+---+#\n",
+                "....|...|.\n",
+                "..#.|...|.\n",
+                "....|..#|.\n",
+                "....|...|.\n",
+                ".#.O^---+.\n",
+                "........#.\n",
+                "#.........\n",
+                "......#...",
+            )
+        );
+    }
+
+    #[test]
+    fn test_patrol_simulation() {
+        let input = advent_of_code::template::read_file("examples", DAY);
+        let mut patrol = Patrol::new(&input, &loop_obstructions(&input));
+        while !patrol.is_done() {
+            patrol.step();
+        }
+        assert_eq!(
+            patrol
+                .path
+                .iter()
+                .map(|&(pos, _)| pos)
+                .collect::<HashSet<_>>()
+                .len(),
+            41
+        );
+        assert_eq!(
+            patrol.render(),
+            concat!(
+                "....#.....\n",
+                "....+---+#\n",
+                "....|...|.\n",
+                "..#.|...|.\n",
+                "..+-+-+#|.\n",
+                "..|.|.|.|.\n",
+                ".#+O+-+-+.\n",
+                ".+----OO#.\n",
+                "#O-O--+|..\n",
+                "......#O..",
+            )
+        );
+    }
 }
@@ -1,104 +1,371 @@
-advent_of_code::solution!(7);
+advent_of_code::variants!(7,
+    1 => [recursive: part_one, bfs: part_one_bfs],
+    2 => [recursive: part_two, bfs: part_two_bfs];
+    generate = tests::random_equations,
+    explain = explain,
+);
+
+use itertools::Itertools;
+use std::fmt;
+use std::num::IntErrorKind;
 
 struct Equation {
     result: u64,
     operands: Vec<u64>,
 }
 
+#[derive(Debug, PartialEq)]
+enum EquationError {
+    /// A number in the input, or the sum of the results, does not fit in a u64.
+    Overflow(String),
+}
+
+impl fmt::Display for EquationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EquationError::Overflow(what) => write!(f, "{what} overflows u64"),
+        }
+    }
+}
+
+/// A binary operator applied left to right, together with its inverse for the backwards search.
+/// Both return `None` when the result would not be a u64.
+trait Operator {
+    fn symbol(&self) -> &str;
+
+    fn apply(&self, left: u64, right: u64) -> Option<u64>;
+
+    /// The left operand that gives `result` when combined with `right`, if there is one.
+    fn undo(&self, result: u64, right: u64) -> Option<u64>;
+
+    /// Whether every left operand gives `result` when combined with `right`, e.g. multiplying
+    /// by zero, so `undo` has no single answer.
+    fn absorbs(&self, _result: u64, _right: u64) -> bool {
+        false
+    }
+
+    /// Whether `apply` never returns less than `left` for a non-zero `right`, so values above
+    /// the result can be dropped.
+    fn is_increasing(&self) -> bool {
+        true
+    }
+}
+
+struct Add;
+struct Mul;
+/// Appends the digits of the right operand, written in `base`, to the left one.
+struct Concat {
+    base: u64,
+}
+
+fn un_add(result: u64, right: u64) -> Option<u64> {
+    result.checked_sub(right)
+}
+
+/// Multiplying by zero loses the left operand, so it cannot be undone; see `Mul::absorbs`.
+fn un_mul(result: u64, right: u64) -> Option<u64> {
+    (right != 0 && result.is_multiple_of(right)).then(|| result / right)
+}
+
+impl Concat {
+    /// `base` to the power of the number of digits in `value`.
+    fn shift(&self, value: u64) -> Option<u64> {
+        self.base
+            .checked_pow(value.checked_ilog(self.base).unwrap_or(0) + 1)
+    }
+
+    fn un_concat(&self, result: u64, right: u64) -> Option<u64> {
+        let shift = self.shift(right)?;
+        (result % shift == right).then(|| result / shift)
+    }
+}
+
+impl Operator for Add {
+    fn symbol(&self) -> &str {
+        "+"
+    }
+
+    fn apply(&self, left: u64, right: u64) -> Option<u64> {
+        left.checked_add(right)
+    }
+
+    fn undo(&self, result: u64, right: u64) -> Option<u64> {
+        un_add(result, right)
+    }
+}
+
+impl Operator for Mul {
+    fn symbol(&self) -> &str {
+        "*"
+    }
+
+    fn apply(&self, left: u64, right: u64) -> Option<u64> {
+        left.checked_mul(right)
+    }
+
+    fn undo(&self, result: u64, right: u64) -> Option<u64> {
+        un_mul(result, right)
+    }
+
+    fn absorbs(&self, result: u64, right: u64) -> bool {
+        result == 0 && right == 0
+    }
+}
+
+impl Operator for Concat {
+    fn symbol(&self) -> &str {
+        "||"
+    }
+
+    fn apply(&self, left: u64, right: u64) -> Option<u64> {
+        left.checked_mul(self.shift(right)?)?.checked_add(right)
+    }
+
+    fn undo(&self, result: u64, right: u64) -> Option<u64> {
+        self.un_concat(result, right)
+    }
+}
+
+fn parse_number(number: &str) -> Result<u64, EquationError> {
+    number
+        .parse()
+        .map_err(|err: std::num::ParseIntError| match err.kind() {
+            IntErrorKind::PosOverflow => EquationError::Overflow(number.to_string()),
+            _ => panic!("{number} should be a number"),
+        })
+}
+
+fn parse(input: &str) -> Result<Vec<Equation>, EquationError> {
+    let mut eqs = vec![];
+    for eq_str in input.lines() {
+        let mut eq_iter = eq_str.split(": ");
+        let result_str = eq_iter.next().expect("result should be present");
+        let operands_str = eq_iter.next().expect("operands should be present");
+        let operands = operands_str
+            .split_whitespace()
+            .map(parse_number)
+            .collect::<Result<_, _>>()?;
+        eqs.push(Equation {
+            result: parse_number(result_str)?,
+            operands,
+        })
+    }
+    Ok(eqs)
+}
+
 struct EquationSolver {
-    concat: bool,
+    /// Tried in order by the backwards search, so the most selective operators go first.
+    operators: Vec<Box<dyn Operator>>,
 }
 
 impl EquationSolver {
     fn new(concat: bool) -> Self {
-        EquationSolver { concat }
+        let mut operators: Vec<Box<dyn Operator>> = vec![Box::new(Mul), Box::new(Add)];
+        if concat {
+            operators.insert(0, Box::new(Concat { base: 10 }));
+        }
+        Self::with_operators(operators)
     }
 
-    fn solve_eq(&self, mut operands: Vec<u64>, result: u64) -> bool {
-        let operand = operands.pop();
-        let operand = match operand {
-            None => return result == 0,
-            Some(operand) => operand,
-        };
+    fn with_operators(operators: Vec<Box<dyn Operator>>) -> Self {
+        EquationSolver { operators }
+    }
 
-        // try concat
-        if self.concat {
-            let op_str = operand.to_string();
-            let result_str = result.to_string();
-            if result_str.ends_with(&op_str) {
-                let (concat_result_str, _) = result_str.split_at(result_str.len() - op_str.len());
-                let concat_result = if concat_result_str == "" {
-                    0
-                } else {
-                    concat_result_str
-                        .parse()
-                        .expect("u64 split into two will fit into u64")
-                };
-                let solved = self.solve_eq(operands.clone(), concat_result);
-                if solved {
-                    return solved;
-                }
-            }
-        }
+    /// Works backwards from the result, undoing the last operator.
+    fn solve_eq(&self, operands: &[u64], result: u64) -> bool {
+        let mut witness = vec![0; operands.len() - 1];
+        self.undo_operators(operands, result, &mut witness)
+    }
 
-        // try div
-        if result % operand == 0 {
-            let div_result = result / operand;
-            let solved = self.solve_eq(operands.clone(), div_result);
-            if solved {
-                return solved;
-            }
+    /// The backwards search of `solve_eq`. On success `witness` holds the index of the operator
+    /// between each pair of operands.
+    fn undo_operators(&self, operands: &[u64], result: u64, witness: &mut [usize]) -> bool {
+        let (&operand, rest) = operands
+            .split_last()
+            .expect("Equations have at least one operand");
+        if rest.is_empty() {
+            return result == operand;
         }
+        self.operators.iter().enumerate().any(|(i, operator)| {
+            witness[rest.len() - 1] = i;
+            if operator.absorbs(result, operand) {
+                let prefix = &mut witness[..rest.len() - 1];
+                return self.apply_operators(rest[0], &rest[1..], prefix);
+            }
+            operator
+                .undo(result, operand)
+                .is_some_and(|left| self.undo_operators(rest, left, witness))
+        })
+    }
 
-        // div did not work - attempt sub
-        let sub_result = result.checked_sub(operand);
-        match sub_result {
-            None => false,
-            Some(sub_result) => self.solve_eq(operands, sub_result),
+    /// Whether the operands following `value` can be combined with it at all, i.e. without
+    /// leaving u64. On success the end of `witness` holds the operators used.
+    fn apply_operators(&self, value: u64, operands: &[u64], witness: &mut [usize]) -> bool {
+        let Some((&operand, rest)) = operands.split_first() else {
+            return true;
+        };
+        let index = witness.len() - operands.len();
+        self.operators.iter().enumerate().any(|(i, operator)| {
+            witness[index] = i;
+            operator
+                .apply(value, operand)
+                .is_some_and(|value| self.apply_operators(value, rest, witness))
+        })
+    }
+
+    /// The operand and operator sequence that gives the result, e.g. `81 + 40 * 27`.
+    fn witness(&self, eq: &Equation) -> Option<String> {
+        let mut witness = vec![0; eq.operands.len() - 1];
+        if !self.undo_operators(&eq.operands, eq.result, &mut witness) {
+            return None;
         }
+        let mut expression = eq.operands[0].to_string();
+        for (&i, operand) in witness.iter().zip(&eq.operands[1..]) {
+            expression.push_str(&format!(" {} {operand}", self.operators[i].symbol()));
+        }
+        Some(expression)
     }
 
-    fn solve_eqs(&self, input: &str) -> u64 {
-        let mut eqs = vec![];
-        for eq_str in input.lines() {
-            let mut eq_iter = eq_str.split(": ");
-            let result_str = eq_iter.next().expect("result should be present");
-            let operands_str = eq_iter.next().expect("operands should be present");
-            let operands: Vec<u64> = operands_str
-                .split_whitespace()
-                .map(|op| op.parse().expect("All should be u32"))
+    /// Works forwards through the operands, keeping every value that does not exceed the result.
+    fn solve_eq_bfs(&self, operands: &[u64], result: u64) -> bool {
+        let (first, rest) = operands
+            .split_first()
+            .expect("Equations have at least one operand");
+        let prune = !rest.contains(&0)
+            && self
+                .operators
+                .iter()
+                .all(|operator| operator.is_increasing());
+        let mut values = vec![*first];
+        for &operand in rest {
+            values = values
+                .into_iter()
+                .flat_map(|value| {
+                    self.operators
+                        .iter()
+                        .filter_map(move |operator| operator.apply(value, operand))
+                })
+                .filter(|&value| !prune || value <= result)
                 .collect();
-            eqs.push(Equation {
-                result: result_str.parse().expect("Should be u32"),
-                operands,
-            })
         }
+        values.contains(&result)
+    }
 
-        let mut result = 0;
-        for eq in eqs {
-            let eq_result = eq.result;
-            if self.solve_eq(eq.operands, eq.result) {
-                result += eq_result
+    fn solve_eqs(
+        &self,
+        input: &str,
+        solve_eq: fn(&Self, &[u64], u64) -> bool,
+    ) -> Result<u64, EquationError> {
+        let mut result: u64 = 0;
+        for eq in parse(input)? {
+            if solve_eq(self, &eq.operands, eq.result) {
+                result = result
+                    .checked_add(eq.result)
+                    .ok_or_else(|| EquationError::Overflow("sum of results".to_string()))?;
             }
         }
+        Ok(result)
+    }
+}
 
-        result
+fn explain(input: &str) {
+    let eqs = match parse(input) {
+        Ok(eqs) => eqs,
+        Err(err) => return eprintln!("{err}"),
+    };
+    for (part, concat) in [(1, false), (2, true)] {
+        println!("Part {part}:");
+        let eq_solver = EquationSolver::new(concat);
+        for eq in &eqs {
+            match eq_solver.witness(eq) {
+                Some(witness) => println!("  {} = {witness}", eq.result),
+                None => println!(
+                    "  {}: {} cannot be solved",
+                    eq.result,
+                    eq.operands.iter().join(" ")
+                ),
+            }
+        }
+    }
+}
+
+fn solve(
+    concat: bool,
+    input: &str,
+    solve_eq: fn(&EquationSolver, &[u64], u64) -> bool,
+) -> Option<u64> {
+    match EquationSolver::new(concat).solve_eqs(input, solve_eq) {
+        Ok(result) => Some(result),
+        Err(err) => {
+            eprintln!("{err}");
+            None
+        }
     }
 }
 
 pub fn part_one(input: &str) -> Option<u64> {
-    let eq_solver = EquationSolver::new(false);
-    Some(eq_solver.solve_eqs(input))
+    solve(false, input, EquationSolver::solve_eq)
+}
+
+pub fn part_one_bfs(input: &str) -> Option<u64> {
+    solve(false, input, EquationSolver::solve_eq_bfs)
 }
 
 pub fn part_two(input: &str) -> Option<u64> {
-    let eq_solver = EquationSolver::new(true);
-    Some(eq_solver.solve_eqs(input))
+    solve(true, input, EquationSolver::solve_eq)
+}
+
+pub fn part_two_bfs(input: &str) -> Option<u64> {
+    solve(true, input, EquationSolver::solve_eq_bfs)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use advent_of_code::template::variants::Rng;
+
+    struct Sub;
+
+    impl Operator for Sub {
+        fn symbol(&self) -> &str {
+            "-"
+        }
+
+        fn apply(&self, left: u64, right: u64) -> Option<u64> {
+            left.checked_sub(right)
+        }
+
+        fn undo(&self, result: u64, right: u64) -> Option<u64> {
+            result.checked_add(right)
+        }
+
+        fn is_increasing(&self) -> bool {
+            false
+        }
+    }
+
+    /// Half of the generated equations are built from a random operator sequence so they are solvable.
+    pub fn random_equations(rng: &mut Rng) -> String {
+        (0..20)
+            .map(|_| {
+                let operands: Vec<u64> = (0..rng.range(1..6)).map(|_| rng.range(0..30)).collect();
+                let result = if rng.bool() {
+                    operands[1..]
+                        .iter()
+                        .fold(operands[0], |acc, &operand| match rng.range(0..3) {
+                            0 => acc + operand,
+                            1 => acc * operand,
+                            _ => format!("{acc}{operand}").parse().unwrap(),
+                        })
+                } else {
+                    rng.range(1..1000)
+                };
+                let operands: Vec<String> = operands.iter().map(u64::to_string).collect();
+                format!("{result}: {}", operands.join(" "))
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
 
     #[test]
     fn test_part_one() {
@@ -111,4 +378,126 @@ mod tests {
         let result = part_two(&advent_of_code::template::read_file("examples", DAY));
         assert_eq!(result, Some(11387));
     }
+
+    #[test]
+    fn test_single_operand_is_not_padded_with_zero() {
+        assert_eq!(part_one("5: 5 5"), Some(0));
+        assert_eq!(part_two("5: 5 5"), Some(0));
+    }
+
+    #[test]
+    fn test_multiplying_by_zero() {
+        for part in [part_one, part_one_bfs, part_two, part_two_bfs] {
+            assert_eq!(part("5: 5 2 0 5\n4: 0 3 0"), Some(5));
+        }
+        let eq_solver = EquationSolver::new(false);
+        assert!(eq_solver.solve_eq(&[3, 0], 0));
+        assert!(eq_solver.solve_eq_bfs(&[3, 0], 0));
+        let eq = Equation {
+            result: 5,
+            operands: vec![5, 2, 0, 5],
+        };
+        assert_eq!(eq_solver.witness(&eq), Some("5 * 2 * 0 + 5".into()));
+    }
+
+    /// Evaluates an expression such as `6 * 8 || 6 * 15` strictly left to right.
+    fn evaluate(expression: &str) -> u64 {
+        let mut tokens = expression.split_whitespace();
+        let mut value: u64 = tokens.next().unwrap().parse().unwrap();
+        while let (Some(operator), Some(operand)) = (tokens.next(), tokens.next()) {
+            value = match operator {
+                "+" => value + operand.parse::<u64>().unwrap(),
+                "*" => value * operand.parse::<u64>().unwrap(),
+                "||" => format!("{value}{operand}").parse().unwrap(),
+                _ => panic!("unknown operator {operator}"),
+            };
+        }
+        value
+    }
+
+    #[test]
+    fn test_witness_expressions() {
+        let eqs = parse(&advent_of_code::template::read_file("examples", DAY)).unwrap();
+        let eq_solver = EquationSolver::new(true);
+        let witnesses: Vec<(u64, String)> = eqs
+            .iter()
+            .filter_map(|eq| Some((eq.result, eq_solver.witness(eq)?)))
+            .collect();
+        assert_eq!(witnesses[1], (3267, "81 + 40 * 27".to_string()));
+        assert_eq!(witnesses.len(), 6);
+        for (result, witness) in &witnesses {
+            assert_eq!(evaluate(witness), *result, "{witness}");
+        }
+    }
+
+    #[test]
+    fn test_witnesses_on_generated_equations() {
+        for seed in 0..50 {
+            let input = random_equations(&mut Rng::new(seed));
+            for concat in [false, true] {
+                let eq_solver = EquationSolver::new(concat);
+                for eq in parse(&input).unwrap() {
+                    let solvable = eq_solver.solve_eq_bfs(&eq.operands, eq.result);
+                    match eq_solver.witness(&eq) {
+                        Some(witness) => {
+                            assert_eq!(evaluate(&witness), eq.result, "{witness}");
+                            assert!(concat || !witness.contains("||"));
+                        }
+                        None => assert!(!solvable, "{}: {:?}", eq.result, eq.operands),
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_operator_inverses() {
+        let concat = Concat { base: 10 };
+        assert_eq!(concat.apply(15, 6), Some(156));
+        assert_eq!(concat.undo(156, 6), Some(15));
+        assert_eq!(concat.undo(156, 56), Some(1));
+        assert_eq!(concat.undo(156, 156), Some(0));
+        assert_eq!(concat.undo(156, 7), None);
+        assert_eq!(concat.apply(u64::MAX / 10, 10), None);
+        assert_eq!(Mul.undo(12, 0), None);
+        assert_eq!(Mul.undo(0, 0), None);
+        assert!(Mul.absorbs(0, 0));
+        assert!(!Mul.absorbs(12, 0));
+        assert_eq!(Add.undo(3, 5), None);
+    }
+
+    #[test]
+    fn test_custom_operator_sets() {
+        let eq = |result, operands: &[u64]| Equation {
+            result,
+            operands: operands.to_vec(),
+        };
+        let with_sub = EquationSolver::with_operators(vec![Box::new(Add), Box::new(Sub)]);
+        assert_eq!(
+            with_sub.witness(&eq(4, &[10, 5, 1])),
+            Some("10 - 5 - 1".into())
+        );
+        assert!(with_sub.solve_eq_bfs(&[10, 5, 1], 4));
+
+        // 0b101 || 0b11 = 0b10111
+        let binary = EquationSolver::with_operators(vec![Box::new(Concat { base: 2 })]);
+        assert_eq!(binary.witness(&eq(23, &[5, 3])), Some("5 || 3".into()));
+        assert_eq!(binary.witness(&eq(53, &[5, 3])), None);
+    }
+
+    #[test]
+    fn test_overflow_is_an_error() {
+        let input = "99999999999999999999: 1 2";
+        assert_eq!(
+            EquationSolver::new(false).solve_eqs(input, EquationSolver::solve_eq),
+            Err(EquationError::Overflow("99999999999999999999".into()))
+        );
+        assert_eq!(part_two(input), None);
+
+        let input = "18446744073709551615: 18446744073709551615\n1: 1";
+        assert_eq!(
+            EquationSolver::new(false).solve_eqs(input, EquationSolver::solve_eq),
+            Err(EquationError::Overflow("sum of results".into()))
+        );
+    }
 }
@@ -1,25 +1,36 @@
-use std::collections::{HashSet, VecDeque};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+use std::iter;
 
-advent_of_code::solution!(9);
+advent_of_code::solution!(9; visualize = visualize);
 
-#[derive(PartialEq, Eq, Hash, Copy, Clone)]
+/// Disks up to this many blocks are traced step by step by `--visualize`.
+const MAX_TRACE_BLOCKS: u64 = 200;
+
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
 enum BlockType {
     File { id: u32 },
     FreeSpace,
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Debug)]
 struct Block {
     size: u32,
     block_type: BlockType,
 }
 
-pub fn part_one(input: &str) -> Option<u64> {
-    let mut map = VecDeque::new();
+/// Sum of `position * id` over a file of `size` blocks starting at `start`.
+fn checksum(id: u64, start: u64, size: u64) -> u64 {
+    id * (size * (2 * start + size).saturating_sub(1) / 2)
+}
+
+/// The disk map as alternating file and free space blocks.
+fn parse(input: &str) -> Vec<Block> {
+    let mut disk = vec![];
     let mut id = 0;
     let mut format = BlockType::File { id };
     for char in input.trim_end().chars() {
-        map.push_back(Block {
+        disk.push(Block {
             size: char.to_digit(10).expect("Fits into u32"),
             block_type: format,
         });
@@ -31,7 +42,14 @@ pub fn part_one(input: &str) -> Option<u64> {
             }
         };
     }
+    disk
+}
 
+/// The disk after moving file blocks one at a time into the leftmost free space, ending with
+/// all of the freed space.
+fn compact_blocks(disk: &[Block]) -> Vec<Block> {
+    let disk_size: u32 = disk.iter().map(|block| block.size).sum();
+    let mut map: VecDeque<_> = disk.iter().cloned().collect();
     let mut new_map = vec![];
     'outer: loop {
         if map.is_empty() {
@@ -86,111 +104,193 @@ pub fn part_one(input: &str) -> Option<u64> {
         }
     }
 
-    let mut total: u64 = 0;
-    let mut start_pos = 0;
-    for block in new_map {
-        let id: u64 = match block.block_type {
-            BlockType::File { id } => id,
-            BlockType::FreeSpace => panic!("Should not have any free space blocks"),
+    let used: u32 = new_map.iter().map(|block| block.size).sum();
+    new_map.push(Block {
+        size: disk_size - used,
+        block_type: BlockType::FreeSpace,
+    });
+    new_map
+}
+
+/// Where a file starts before and after compaction, in blocks.
+struct FileMove {
+    id: u32,
+    from: u64,
+    to: u64,
+    size: u32,
+}
+
+/// Moves whole files, highest id first, into the leftmost free span that fits them.
+fn move_files(disk: &[Block]) -> Vec<FileMove> {
+    let mut files = vec![];
+    // free spans by size, each a min-heap of start positions so the leftmost span is on top.
+    let mut free_spans: [BinaryHeap<Reverse<u64>>; 10] = Default::default();
+    let mut position = 0;
+    for block in disk {
+        match block.block_type {
+            BlockType::File { id } => files.push((id, position, block.size)),
+            BlockType::FreeSpace if block.size > 0 => {
+                free_spans[block.size as usize].push(Reverse(position))
+            }
+            BlockType::FreeSpace => {}
         }
-        .try_into()
-        .expect("Fits into u64");
-        let block_size: u64 = block.size.try_into().expect("Fits into u64");
-        total += (start_pos..(start_pos + block_size)).sum::<u64>() * id;
-        start_pos += block_size;
+        position += u64::from(block.size);
     }
-    Some(total)
+
+    files
+        .into_iter()
+        .rev()
+        .map(|(id, from, size)| {
+            let leftmost_span = (size as usize..free_spans.len())
+                .filter_map(|span_size| {
+                    let &Reverse(start) = free_spans[span_size].peek()?;
+                    (start < from).then_some((start, span_size))
+                })
+                .min();
+            let to = match leftmost_span {
+                Some((start, span_size)) => {
+                    free_spans[span_size].pop();
+                    let remaining = span_size - size as usize;
+                    if remaining > 0 {
+                        free_spans[remaining].push(Reverse(start + u64::from(size)));
+                    }
+                    start
+                }
+                None => from,
+            };
+            FileMove { id, from, to, size }
+        })
+        .collect()
 }
 
-pub fn part_two(input: &str) -> Option<u64> {
-    let mut map = VecDeque::new();
-    let mut id = 0;
-    let mut format = BlockType::File { id };
-    let mut files = vec![];
-    for char in input.trim_end().chars() {
-        let char_int = char.to_digit(10).expect("Fits into u32");
-        map.push_back(Block {
-            size: char_int,
-            block_type: format,
+/// The disk after moving whole files.
+fn compact_files(disk: &[Block]) -> Vec<Block> {
+    let disk_size: u64 = disk.iter().map(|block| u64::from(block.size)).sum();
+    let mut moves = move_files(disk);
+    moves.sort_by_key(|file| file.to);
+    let mut blocks = vec![];
+    let mut position = 0;
+    let free_space = |blocks: &mut Vec<Block>, size: u64| {
+        if size > 0 {
+            blocks.push(Block {
+                size: size.try_into().expect("Fits into u32"),
+                block_type: BlockType::FreeSpace,
+            });
+        }
+    };
+    for file in moves {
+        free_space(&mut blocks, file.to - position);
+        blocks.push(Block {
+            size: file.size,
+            block_type: BlockType::File { id: file.id },
         });
-        format = match format {
-            BlockType::File { .. } => {
-                files.push(Block {
-                    size: char_int,
-                    block_type: format,
-                });
-
-                BlockType::FreeSpace
-            }
-            BlockType::FreeSpace => {
-                id += 1;
-                BlockType::File { id }
-            }
-        };
+        position = file.to + u64::from(file.size);
     }
+    free_space(&mut blocks, disk_size - position);
+    blocks
+}
 
-    let mut used_files = HashSet::new();
-    let mut new_map = vec![];
+/// One entry per block on the disk: the id of the file using it, if any.
+fn cells(blocks: &[Block]) -> Vec<Option<u32>> {
+    blocks
+        .iter()
+        .flat_map(|block| {
+            let id = match block.block_type {
+                BlockType::File { id } => Some(id),
+                BlockType::FreeSpace => None,
+            };
+            iter::repeat_n(id, block.size as usize)
+        })
+        .collect()
+}
 
+/// The puzzle's notation, `00...111...2...`, showing the last digit of ids of 10 and above.
+fn render_cells(cells: &[Option<u32>]) -> String {
+    cells
+        .iter()
+        .map(|cell| match cell {
+            Some(id) => char::from_digit(id % 10, 10).expect("single digit"),
+            None => '.',
+        })
+        .collect()
+}
+
+fn render(blocks: &[Block]) -> String {
+    render_cells(&cells(blocks))
+}
+
+/// The disk after each block moved by part one, starting with the disk as given.
+fn trace_blocks(disk: &[Block]) -> Vec<String> {
+    let mut cells = cells(disk);
+    let mut trace = vec![render_cells(&cells)];
+    let mut free = 0;
+    let mut file = cells.len();
     loop {
-        if map.is_empty() {
-            break;
-        };
-        let next_block = map
-            .pop_front()
-            .expect("Unless input is empty this should be fine");
-        match next_block.block_type {
-            BlockType::File { id } if used_files.contains(&id) => new_map.push(Block {
-                size: next_block.size,
-                block_type: BlockType::FreeSpace,
-            }),
-            BlockType::File { id } => {
-                used_files.insert(id);
-                new_map.push(next_block);
-            }
-            BlockType::FreeSpace => {
-                let mut matched_file = false;
-                for block in files.iter().rev() {
-                    let id = match block.block_type {
-                        BlockType::File { id } => id,
-                        BlockType::FreeSpace => panic!("Should not have any free space blocks"),
-                    };
-                    if used_files.contains(&id) {
-                        continue;
-                    }
-                    if block.size > next_block.size {
-                        continue;
-                    }
-                    matched_file = true;
-                    used_files.insert(id);
-                    new_map.push((*block).clone());
-                    let empty_block_size = next_block.size - block.size;
-                    if empty_block_size > 0 {
-                        map.push_front(Block {
-                            size: empty_block_size,
-                            block_type: BlockType::FreeSpace,
-                        });
-                    };
-                    break;
-                }
-                if !matched_file {
-                    // no files could fit into the empty space
-                    // so we keep the empty space block
-                    new_map.push(next_block);
-                }
-            }
+        while free < cells.len() && cells[free].is_some() {
+            free += 1;
+        }
+        while file > 0 && cells[file - 1].is_none() {
+            file -= 1;
+        }
+        if file == 0 || free >= file - 1 {
+            return trace;
         }
+        cells.swap(free, file - 1);
+        trace.push(render_cells(&cells));
     }
+}
+
+/// The disk after each file moved by part two, starting with the disk as given.
+fn trace_files(disk: &[Block]) -> Vec<String> {
+    let mut cells = cells(disk);
+    let mut trace = vec![render_cells(&cells)];
+    for file in move_files(disk) {
+        if file.from == file.to {
+            continue;
+        }
+        let (from, to, size) = (file.from as usize, file.to as usize, file.size as usize);
+        cells[to..to + size].fill(Some(file.id));
+        cells[from..from + size].fill(None);
+        trace.push(render_cells(&cells));
+    }
+    trace
+}
+
+fn visualize(input: &str) {
+    let disk = parse(input);
+    let disk_size: u64 = disk.iter().map(|block| u64::from(block.size)).sum();
+    if disk_size <= MAX_TRACE_BLOCKS {
+        println!("Moving blocks:\n{}", trace_blocks(&disk).join("\n"));
+        println!("\nMoving files:\n{}", trace_files(&disk).join("\n"));
+    } else {
+        println!("Moving blocks:\n{}", render(&compact_blocks(&disk)));
+        println!("\nMoving files:\n{}", render(&compact_files(&disk)));
+    }
+}
+
+/// Checksum of a whole disk layout.
+fn disk_checksum(blocks: &[Block]) -> u64 {
     let mut total: u64 = 0;
     let mut start_pos = 0;
-    for block in new_map {
-        let block_size: u64 = block.size.try_into().expect("Fits into u64");
+    for block in blocks {
+        let block_size: u64 = block.size.into();
         if let BlockType::File { id } = block.block_type {
-            let file_id: u64 = id.try_into().expect("Fits into u64");
-            total += (start_pos..(start_pos + block_size)).sum::<u64>() * file_id;
+            total += checksum(id.into(), start_pos, block_size);
         }
         start_pos += block_size;
     }
+    total
+}
+
+pub fn part_one(input: &str) -> Option<u64> {
+    Some(disk_checksum(&compact_blocks(&parse(input))))
+}
+
+pub fn part_two(input: &str) -> Option<u64> {
+    let total = move_files(&parse(input))
+        .into_iter()
+        .map(|file| checksum(file.id.into(), file.to, file.size.into()))
+        .sum();
     Some(total)
 }
 
@@ -220,4 +320,56 @@ mod tests {
         ));
         assert_eq!(result, Some(132));
     }
+
+    #[test]
+    fn test_checksum() {
+        for (start, size) in [(0, 0), (0, 1), (7, 0), (3, 5), (1000, 9)] {
+            let expected: u64 = (start..start + size).map(|position| position * 11).sum();
+            assert_eq!(checksum(11, start, size), expected);
+        }
+    }
+
+    #[test]
+    fn test_compact_blocks_layout() {
+        let disk = parse(&advent_of_code::template::read_file_part(
+            "examples", DAY, 1,
+        ));
+        let blocks = compact_blocks(&disk);
+        let file = |size, id| Block {
+            size,
+            block_type: BlockType::File { id },
+        };
+        assert_eq!(blocks[..3], [file(2, 0), file(2, 9), file(1, 8)]);
+        assert_eq!(
+            render(&blocks),
+            "0099811188827773336446555566.............."
+        );
+
+        let trace = trace_blocks(&disk);
+        assert_eq!(trace[1], "009..111...2...333.44.5555.6666.777.88889.");
+        assert_eq!(trace.last(), Some(&render(&blocks)));
+    }
+
+    #[test]
+    fn test_compact_files_layout() {
+        let disk = parse(&advent_of_code::template::read_file_part(
+            "examples", DAY, 1,
+        ));
+        let blocks = compact_files(&disk);
+        assert_eq!(
+            render(&blocks),
+            "00992111777.44.333....5555.6666.....8888.."
+        );
+        assert_eq!(
+            trace_files(&disk),
+            [
+                "00...111...2...333.44.5555.6666.777.888899",
+                "0099.111...2...333.44.5555.6666.777.8888..",
+                "0099.1117772...333.44.5555.6666.....8888..",
+                "0099.111777244.333....5555.6666.....8888..",
+                "00992111777.44.333....5555.6666.....8888..",
+            ]
+        );
+        assert_eq!(disk_checksum(&blocks), 2858);
+    }
 }
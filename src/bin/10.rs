@@ -1,121 +1,283 @@
-use std::{collections::HashMap, ops::Add};
+use advent_of_code::grid::{Grid, Position, Render};
+use advent_of_code::image::{Palette, Rgb};
+use advent_of_code::simulation::{self, Simulation};
+use std::collections::HashSet;
+use std::ops::Range;
 
-advent_of_code::solution!(10);
+advent_of_code::solution!(10; visualize = visualize, explain = explain);
 
-#[derive(PartialEq, Eq, Hash, Clone, Copy)]
-struct Point {
-    x: i32,
-    y: i32,
+const PEAK: u8 = 9;
+
+/// A topographic map of heights, where `.` marks an impassable cell.
+fn parse(input: &str) -> Grid<Option<u8>> {
+    Grid::parse(input, |char| match char {
+        '.' => None,
+        _ => Some(
+            char.to_digit(10)
+                .expect("Heights are digits")
+                .try_into()
+                .expect("Fits into u8"),
+        ),
+    })
+}
+
+/// Neighbours off the top or left edge wrap around to positions outside the grid.
+fn neighbours((x, y): Position) -> [Position; 4] {
+    [
+        (x, y + 1),
+        (x, y.wrapping_sub(1)),
+        (x + 1, y),
+        (x.wrapping_sub(1), y),
+    ]
 }
 
-impl Add for Point {
-    type Output = Self;
+/// A set of peaks as bits indexed by peak number, with peaks numbered in reading order.
+///
+/// Only the words from `start` onwards that the set was created to cover are stored, so a cell
+/// can keep just the peaks within a trail's reach of its row.
+struct PeakSet {
+    start: usize,
+    words: Vec<u64>,
+}
 
-    fn add(self, other: Self) -> Self {
+impl PeakSet {
+    fn covering(peaks: Range<usize>) -> Self {
+        let start = peaks.start / 64;
         Self {
-            x: self.x + other.x,
-            y: self.y + other.y,
+            start,
+            words: vec![0; peaks.end.div_ceil(64) - start],
         }
     }
-}
 
-#[derive(PartialEq, Eq, Hash)]
-struct Path {
-    start: Point,
-    end: Point,
+    fn insert(&mut self, peak: usize) {
+        self.words[peak / 64 - self.start] |= 1 << (peak % 64);
+    }
+
+    /// Adds the peaks of `other`, which must all lie in the range this set covers.
+    fn union(&mut self, other: &Self) {
+        for (i, &word) in other.words.iter().enumerate() {
+            match self
+                .words
+                .get_mut((other.start + i).wrapping_sub(self.start))
+            {
+                Some(own) => *own |= word,
+                None => debug_assert_eq!(word, 0, "Peak outside the covered range"),
+            }
+        }
+    }
+
+    fn len(&self) -> u32 {
+        self.words.iter().map(|word| word.count_ones()).sum()
+    }
 }
 
-const DIRS: [Point; 4] = [
-    Point { x: 0, y: 1 },
-    Point { x: 0, y: -1 },
-    Point { x: 1, y: 0 },
-    Point { x: -1, y: 0 },
-];
+#[derive(Debug, PartialEq, Eq)]
+struct Trailhead {
+    position: Position,
+    score: u32,
+    rating: u32,
+}
 
-struct MapData {
-    map: HashMap<Point, u32>,
-    starting_points: Vec<Point>,
+/// Every trail of a topographic map.
+struct Trails {
+    map: Grid<Option<u8>>,
+    /// The number of trails from every cell to a peak. A cell's trails continue through its
+    /// uphill neighbours that have trails of their own, so following those retraces them all.
+    trails: Grid<u32>,
+    /// In reading order.
+    trailheads: Vec<Trailhead>,
 }
 
-fn parse(input: &str) -> MapData {
-    let mut map = HashMap::new();
-    let mut starting_points = vec![];
-    for (y, row) in input.lines().enumerate() {
-        for (x, char) in row.chars().enumerate() {
-            let point = Point {
-                x: x.try_into().expect("Fits into i32"),
-                y: y.try_into().expect("Fits into i32"),
+impl Trails {
+    /// Works down from the peaks one height at a time. A cell reaches the peaks its uphill
+    /// neighbours reach, and its trails are theirs added together, so each layer is computed
+    /// from the one above it.
+    ///
+    /// Every step of a trail moves at most one row, so a cell only needs the peaks within
+    /// `PEAK` rows of it. Its peak set covers just those, and only the sets of two layers are
+    /// kept at once, which keeps the memory for them proportional to the width of the map
+    /// rather than its area.
+    fn new(map: Grid<Option<u8>>) -> Self {
+        let mut layers = vec![vec![]; usize::from(PEAK) + 1];
+        // The index of every cell within its layer.
+        let mut layer_index = Grid::from_fn(map.width(), map.height(), |_| 0);
+        // The number of peaks before each row, and in total at the end.
+        let mut peaks_before_row = vec![0; map.height() + 1];
+        for (position, &height) in map.iter() {
+            let Some(height) = height else {
+                continue;
             };
-            let height = char.to_digit(10).expect("Fits into u32");
-            map.insert(point, height);
-            if height == 0 {
-                starting_points.push(point);
+            let layer = &mut layers[usize::from(height)];
+            layer_index[position] = layer.len();
+            layer.push(position);
+            if height == PEAK {
+                peaks_before_row[position.1 + 1] += 1;
             }
         }
+        for row in 1..peaks_before_row.len() {
+            peaks_before_row[row] += peaks_before_row[row - 1];
+        }
+
+        let reachable_peaks = |(_, y): Position| {
+            let reach = usize::from(PEAK);
+            peaks_before_row[y.saturating_sub(reach)]
+                ..peaks_before_row[(y + reach + 1).min(map.height())]
+        };
+
+        let mut trails = Grid::from_fn(map.width(), map.height(), |_| 0);
+        let mut peaks: Vec<PeakSet> = layers[usize::from(PEAK)]
+            .iter()
+            .enumerate()
+            .map(|(peak, &position)| {
+                trails[position] = 1;
+                let mut peaks = PeakSet::covering(reachable_peaks(position));
+                peaks.insert(peak);
+                peaks
+            })
+            .collect();
+
+        for height in (0..PEAK).rev() {
+            peaks = layers[usize::from(height)]
+                .iter()
+                .map(|&position| {
+                    let mut cell_peaks = PeakSet::covering(reachable_peaks(position));
+                    for neighbour in uphill(&map, position) {
+                        cell_peaks.union(&peaks[layer_index[neighbour]]);
+                        trails[position] += trails[neighbour];
+                    }
+                    cell_peaks
+                })
+                .collect();
+        }
+
+        let trailheads = layers[0]
+            .iter()
+            .zip(peaks)
+            .map(|(&position, peaks)| Trailhead {
+                position,
+                score: peaks.len(),
+                rating: trails[position],
+            })
+            .collect();
+
+        Self {
+            map,
+            trails,
+            trailheads,
+        }
     }
-    MapData {
-        map,
-        starting_points,
+
+    /// Every distinct trail from `trailhead` to a peak, as the cells it passes through.
+    fn distinct_trails(&self, trailhead: Position) -> Vec<Vec<Position>> {
+        let mut trails = vec![];
+        let mut stack = vec![vec![trailhead]];
+        while let Some(trail) = stack.pop() {
+            let end = *trail.last().expect("Trails start at the trailhead");
+            if self.map[end] == Some(PEAK) {
+                trails.push(trail);
+                continue;
+            }
+            for next in uphill(&self.map, end).filter(|&next| self.trails[next] > 0) {
+                let mut longer = trail.clone();
+                longer.push(next);
+                stack.push(longer);
+            }
+        }
+        trails
+    }
+
+    /// Draws the heights of the given cells, as in the puzzle's figures, and `.` elsewhere.
+    fn render(&self, cells: impl IntoIterator<Item = Position>) -> Render<'_> {
+        let cells: HashSet<Position> = cells.into_iter().collect();
+        Render::new(
+            self.map.width(),
+            self.map.height(),
+            move |position| match self.map[position] {
+                Some(height) if cells.contains(&position) => {
+                    char::from_digit(height.into(), 10).expect("Heights are digits")
+                }
+                _ => '.',
+            },
+        )
     }
 }
 
-struct MapSolver<'a> {
-    map_data: &'a MapData,
-    paths: HashMap<Path, u32>,
+/// The neighbours of a cell one higher than it.
+fn uphill(map: &Grid<Option<u8>>, position: Position) -> impl Iterator<Item = Position> + '_ {
+    let height = map[position].map(|height| height + 1);
+    neighbours(position)
+        .into_iter()
+        .filter(move |&neighbour| height.is_some() && map.get(neighbour) == Some(&height))
 }
 
-impl<'a> MapSolver<'a> {
-    fn new(map_: &'a MapData) -> Self {
-        Self {
-            map_data: map_,
-            paths: HashMap::new(),
-        }
+/// Shows the trails from one trailhead at a time, in reading order.
+struct TrailheadTour<'a> {
+    trails: &'a Trails,
+    current: usize,
+}
+
+impl Simulation for TrailheadTour<'_> {
+    fn step(&mut self) {
+        self.current += 1;
     }
 
-    fn solve_path(&mut self, pos: &Point, height: u32, start: &Point) {
-        if height == 9 {
-            let count = self
-                .paths
-                .entry(Path {
-                    start: *start,
-                    end: *pos,
-                })
-                .or_insert(0);
-            *count += 1;
-            return;
-        }
-        for dir in DIRS.iter() {
-            let new_pos = *pos + *dir;
-            let new_height = match self.map_data.map.get(&new_pos) {
-                Some(new_height) if new_height == &(height + 1) => *new_height,
-                _ => continue,
-            };
-            self.solve_path(&new_pos, new_height, start);
-        }
+    fn render(&self) -> String {
+        let cells = self
+            .trails
+            .trailheads
+            .get(self.current)
+            .map(|trailhead| self.trails.distinct_trails(trailhead.position))
+            .unwrap_or_default();
+        self.trails.render(cells.into_iter().flatten()).to_string()
     }
 
-    fn solve(&mut self) {
-        for start in self.map_data.starting_points.iter() {
-            self.solve_path(start, 0, start);
-        }
+    fn is_done(&self) -> bool {
+        self.current + 1 >= self.trails.trailheads.len()
     }
 }
 
-fn solve(input: &str) -> HashMap<Path, u32> {
-    let map_data = parse(input);
-    let mut solver = MapSolver::new(&map_data);
-    solver.solve();
-    solver.paths
+/// Shows the trails from each trailhead in turn; `--jump <n>` starts at the n-th trailhead.
+fn visualize(input: &str) {
+    let palette = (0..=PEAK).fold(
+        Palette::default().with('.', Rgb(24, 24, 24)),
+        |palette, height| {
+            let shade = 60 + 20 * height;
+            palette.with(
+                char::from_digit(height.into(), 10).expect("Heights are digits"),
+                Rgb(shade / 2, shade, shade / 3),
+            )
+        },
+    );
+    let trails = Trails::new(parse(input));
+    simulation::visualize(
+        &mut TrailheadTour {
+            trails: &trails,
+            current: 0,
+        },
+        &palette,
+    );
+}
+
+/// Lists the score and rating of every trailhead, in reading order.
+fn explain(input: &str) {
+    let trails = Trails::new(parse(input));
+    for (i, trailhead) in trails.trailheads.iter().enumerate() {
+        let (x, y) = trailhead.position;
+        println!(
+            "Trailhead {i} at ({x}, {y}): score {}, rating {}",
+            trailhead.score, trailhead.rating
+        );
+    }
 }
 
 pub fn part_one(input: &str) -> Option<u32> {
-    let result = solve(input);
-    Some(result.len().try_into().expect("Fits into u32"))
+    let trails = Trails::new(parse(input));
+    Some(trails.trailheads.iter().map(|t| t.score).sum())
 }
 
 pub fn part_two(input: &str) -> Option<u32> {
-    let result = solve(input);
-    Some(result.values().sum())
+    let trails = Trails::new(parse(input));
+    Some(trails.trailheads.iter().map(|t| t.rating).sum())
 }
 
 #[cfg(test)]
@@ -133,4 +295,129 @@ mod tests {
         let result = part_two(&advent_of_code::template::read_file("examples", DAY));
         assert_eq!(result, Some(81));
     }
+
+    #[test]
+    fn test_impassable_examples() {
+        let scores = |part| {
+            let input = advent_of_code::template::read_file_part("examples", DAY, part);
+            let trails = Trails::new(parse(&input));
+            trails
+                .trailheads
+                .iter()
+                .map(|t| t.score)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(scores(1), [2]);
+        assert_eq!(scores(2), [4]);
+        assert_eq!(scores(3), [1, 2]);
+
+        let ratings = |part| {
+            let input = advent_of_code::template::read_file_part("examples", DAY, part);
+            part_two(&input)
+        };
+        assert_eq!(ratings(2), Some(13));
+        assert_eq!(ratings(4), Some(3));
+        assert_eq!(ratings(5), Some(227));
+    }
+
+    #[test]
+    fn test_trailheads() {
+        let trails = Trails::new(parse(&advent_of_code::template::read_file("examples", DAY)));
+        let scores: Vec<_> = trails.trailheads.iter().map(|t| t.score).collect();
+        assert_eq!(scores, [5, 6, 5, 3, 1, 3, 5, 3, 5]);
+        let ratings: Vec<_> = trails.trailheads.iter().map(|t| t.rating).collect();
+        assert_eq!(ratings, [20, 24, 10, 4, 1, 4, 5, 8, 5]);
+        assert_eq!(
+            trails.trailheads[0],
+            Trailhead {
+                position: (2, 0),
+                score: 5,
+                rating: 20
+            }
+        );
+    }
+
+    #[test]
+    fn test_distinct_trails() {
+        let trails = Trails::new(parse(&advent_of_code::template::read_file_part(
+            "examples", DAY, 4,
+        )));
+        let mut renders: Vec<_> = trails
+            .distinct_trails((5, 0))
+            .into_iter()
+            .map(|trail| trails.render(trail).to_string())
+            .collect();
+        renders.sort();
+        assert_eq!(
+            renders,
+            [
+                concat!(
+                    ".....0.\n",
+                    ".....1.\n",
+                    ".....2.\n",
+                    ".....3.\n",
+                    ".....4.\n",
+                    "..8765.\n",
+                    "..9....",
+                ),
+                concat!(
+                    ".....0.\n",
+                    ".....1.\n",
+                    ".....2.\n",
+                    "..6543.\n",
+                    "..7....\n",
+                    "..8....\n",
+                    "..9....",
+                ),
+                concat!(
+                    ".....0.\n",
+                    "..4321.\n",
+                    "..5....\n",
+                    "..6....\n",
+                    "..7....\n",
+                    "..8....\n",
+                    "..9....",
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_trailhead_tour() {
+        let trails = Trails::new(parse(&advent_of_code::template::read_file_part(
+            "examples", DAY, 3,
+        )));
+        let mut tour = TrailheadTour {
+            trails: &trails,
+            current: 0,
+        };
+        assert_eq!(
+            tour.render(),
+            "10.....\n2......\n3......\n4567...\n...8...\n...9...\n......."
+        );
+        assert!(!tour.is_done());
+        tour.step();
+        assert!(tour.is_done());
+        assert_eq!(
+            tour.render(),
+            "....9..\n....8..\n....7..\n...7654\n...8..3\n...9..2\n.....01"
+        );
+    }
+
+    /// Tiles the example many times over, separated by impassable cells so the tiles are
+    /// independent, with far more peaks than fit in one row's reach.
+    #[test]
+    fn test_large_map() {
+        let example = advent_of_code::template::read_file("examples", DAY);
+        let tiles = 25;
+        let tile_row: String = example
+            .lines()
+            .chain(["........"])
+            .map(|line| format!("{}\n", format!("{line}.").repeat(tiles)))
+            .collect();
+        let input = tile_row.repeat(tiles);
+        let tiles = u32::try_from(tiles * tiles).expect("Fits into u32");
+        assert_eq!(part_one(&input), Some(36 * tiles));
+        assert_eq!(part_two(&input), Some(81 * tiles));
+    }
 }
@@ -1,60 +1,135 @@
+use advent_of_code::multiset::{self, Count, Multiset, Transformer};
+use advent_of_code::template::runner::RunState;
 use std::collections::HashMap;
+use std::iter;
 
-advent_of_code::solution!(11);
+advent_of_code::variants!(11,
+    1 => [multiset: part_one, cached: part_one_cached],
+    2 => [multiset: part_two, cached: part_two_cached];
+    explain = explain,
+);
 
-fn parse(input: &str) -> HashMap<u64, u64> {
-    let mut stones = HashMap::new();
-    for stone in input
-        .trim_end()
+const POWERS_OF_TEN: [u64; 20] = {
+    let mut powers = [1; 20];
+    let mut i = 1;
+    while i < powers.len() {
+        powers[i] = powers[i - 1] * 10;
+        i += 1;
+    }
+    powers
+};
+
+fn parse<C: Count>(input: &str) -> Multiset<u64, C> {
+    input
         .split_whitespace()
         .map(|x| x.parse().unwrap())
-    {
-        let count = stones.entry(stone).or_insert(0);
-        *count += 1;
+        .collect()
+}
+
+/// The left and right halves of the digits of a stone with an even number of digits.
+fn split_digits(stone: u64) -> Option<(u64, u64)> {
+    let digits = stone.checked_ilog10()? + 1;
+    if digits % 2 != 0 {
+        return None;
     }
-    stones
+    let half = POWERS_OF_TEN[digits as usize / 2];
+    Some((stone / half, stone % half))
 }
 
-fn solve_stone(num: u64) -> Vec<u64> {
-    if num == 0 {
-        return vec![1];
+/// The one or two stones a stone turns into when blinking.
+fn blink(stone: u64) -> (u64, Option<u64>) {
+    if stone == 0 {
+        return (1, None);
     }
-    let num_str = num.to_string();
-    if num_str.len() % 2 == 0 {
-        let half = num_str.len() / 2;
-        return vec![
-            num_str[..half].parse().unwrap(),
-            num_str[half..].parse().unwrap(),
-        ];
+    match split_digits(stone) {
+        Some((left, right)) => (left, Some(right)),
+        None => (stone * 2024, None),
     }
-    vec![num * 2024]
 }
 
-fn solve_stones(mut nums: HashMap<u64, u64>, n: u64) -> u64 {
-    for _ in 0..n {
-        let mut new_nums = HashMap::new();
-        for (num, count) in nums.into_iter() {
-            for new_num in solve_stone(num.try_into().expect("Fits into u64")) {
-                let new_count = new_nums.entry(new_num).or_insert(0);
-                *new_count += count;
-            }
+fn solve_stone(num: &u64) -> Vec<u64> {
+    let (first, second) = blink(*num);
+    iter::once(first).chain(second).collect()
+}
+
+/// Counts stones depth first, remembering the count for every stone and number of blinks.
+/// Counting part one with a counter leaves it warm for part two, whose shallower subproblems
+/// are then answered from the cache.
+#[derive(Default)]
+struct StoneCounter<C> {
+    /// Indexed by the number of blinks and then keyed by the stone.
+    cache: Vec<HashMap<u64, C>>,
+}
+
+impl<C: Count> StoneCounter<C> {
+    fn count_stone(&mut self, stone: u64, blinks: usize) -> C {
+        if blinks == 0 {
+            return C::from(1);
+        }
+        if self.cache.len() <= blinks {
+            self.cache.resize_with(blinks + 1, HashMap::new);
+        }
+        if let Some(count) = self.cache[blinks].get(&stone) {
+            return count.clone();
         }
-        nums = new_nums;
+        let (first, second) = blink(stone);
+        let mut count = self.count_stone(first, blinks - 1);
+        if let Some(second) = second {
+            count = multiset::add(&count, &self.count_stone(second, blinks - 1));
+        }
+        self.cache[blinks].insert(stone, count.clone());
+        count
+    }
+
+    /// Like the free function `count_stones`, but depth first through the cache.
+    fn count_stones(&mut self, input: &str, blinks: usize) -> C {
+        input
+            .split_whitespace()
+            .map(|stone| self.count_stone(stone.parse().unwrap(), blinks))
+            .fold(C::default(), |total, count| multiset::add(&total, &count))
     }
-    nums.values().sum()
 }
 
-fn solve(input: &str, n: u64) -> u64 {
-    let stones = parse(input);
-    solve_stones(stones, n)
+/// Shared by the cached parts, so part one warms part two within a run.
+static STONE_COUNTER: RunState<StoneCounter<u64>> = RunState::new();
+
+/// The number of stones after `blinks` blinks.
+fn count_stones<C: Count>(input: &str, blinks: usize) -> C {
+    Transformer::new(solve_stone)
+        .run(parse::<C>(input), blinks)
+        .total()
+}
+
+/// The number of stones and of distinct stone values after each blink, starting before the
+/// first one.
+fn blink_stats<C: Count>(input: &str, blinks: usize) -> Vec<(C, usize)> {
+    Transformer::new(solve_stone)
+        .steps(parse::<C>(input))
+        .take(blinks + 1)
+        .map(|stones| (stones.total(), stones.distinct()))
+        .collect()
+}
+
+fn explain(input: &str) {
+    for (blink, (stones, distinct)) in blink_stats::<u128>(input, 75).into_iter().enumerate() {
+        println!("Blink {blink}: {stones} stones, {distinct} distinct");
+    }
 }
 
 pub fn part_one(input: &str) -> Option<u64> {
-    Some(solve(input, 25))
+    Some(count_stones(input, 25))
+}
+
+pub fn part_one_cached(input: &str) -> Option<u64> {
+    Some(STONE_COUNTER.with(|counter| counter.count_stones(input, 25)))
 }
 
 pub fn part_two(input: &str) -> Option<u64> {
-    Some(solve(input, 75))
+    Some(count_stones(input, 75))
+}
+
+pub fn part_two_cached(input: &str) -> Option<u64> {
+    Some(STONE_COUNTER.with(|counter| counter.count_stones(input, 75)))
 }
 
 #[cfg(test)]
@@ -66,4 +141,78 @@ mod tests {
         let result = part_one(&advent_of_code::template::read_file("examples", DAY));
         assert_eq!(result, Some(55312));
     }
+
+    #[test]
+    fn test_part_two() {
+        let result = part_two(&advent_of_code::template::read_file("examples", DAY));
+        assert_eq!(result, Some(65601038650482));
+    }
+
+    #[test]
+    fn test_blink_stats() {
+        let input = advent_of_code::template::read_file("examples", DAY);
+        assert_eq!(
+            blink_stats::<u64>(&input, 6),
+            [(2, 2), (3, 3), (4, 4), (5, 5), (9, 8), (13, 12), (22, 15)]
+        );
+    }
+
+    #[test]
+    fn test_deep_blinks() {
+        use num_bigint::BigUint;
+
+        let input = advent_of_code::template::read_file("examples", DAY);
+        let stones: u128 = count_stones(&input, 100);
+        assert_eq!(count_stones::<BigUint>(&input, 100), BigUint::from(stones));
+        assert!(count_stones::<BigUint>(&input, 250) > BigUint::from(u128::MAX));
+    }
+
+    #[test]
+    fn test_warm_counter() {
+        let input = advent_of_code::template::read_file("examples", DAY);
+        let mut counter = StoneCounter::<u64>::default();
+        assert_eq!(counter.count_stones(&input, 25), 55312);
+        assert_eq!(counter.count_stones(&input, 75), 65601038650482);
+    }
+
+    #[test]
+    fn test_cached_parts_share_a_counter() {
+        let input = advent_of_code::template::read_file("examples", DAY);
+        assert_eq!(part_one_cached(&input), Some(55312));
+        // both input stones after 25 blinks, ready for part two
+        let warm = STONE_COUNTER.with(|counter| counter.cache[25].len());
+        assert_eq!(warm, 2);
+        assert_eq!(part_two_cached(&input), Some(65601038650482));
+        assert!(STONE_COUNTER.with(|counter| counter.cache[25].len()) > warm);
+    }
+
+    #[test]
+    fn test_cached_deep_blinks() {
+        use num_bigint::BigUint;
+
+        let input = advent_of_code::template::read_file("examples", DAY);
+        let stones: BigUint = StoneCounter::default().count_stones(&input, 250);
+        assert_eq!(stones, count_stones::<BigUint>(&input, 250));
+    }
+
+    #[test]
+    #[should_panic(expected = "Count overflowed")]
+    fn test_cached_overflow() {
+        let input = advent_of_code::template::read_file("examples", DAY);
+        StoneCounter::<u64>::default().count_stones(&input, 250);
+    }
+
+    #[test]
+    fn test_split_digits() {
+        assert_eq!(split_digits(0), None);
+        assert_eq!(split_digits(7), None);
+        assert_eq!(split_digits(1000), Some((10, 0)));
+        assert_eq!(split_digits(253000), Some((253, 0)));
+        assert_eq!(split_digits(123), None);
+        assert_eq!(split_digits(u64::MAX), Some((1844674407, 3709551615)));
+        assert_eq!(
+            split_digits(10_000_000_000_000_000_000),
+            Some((1_000_000_000, 0))
+        );
+    }
 }
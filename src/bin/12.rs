@@ -1,9 +1,12 @@
+use advent_of_code::image::{Image, Rgb};
+use advent_of_code::search;
+use advent_of_code::simulation::FrameExport;
 use std::{
     collections::{HashMap, HashSet},
     ops::Add,
 };
 
-advent_of_code::solution!(12);
+advent_of_code::solution!(12; visualize = visualize, explain = explain);
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 struct Point {
@@ -30,6 +33,7 @@ const DIRECTIONS: [[Point; 2]; 4] = [
 ];
 
 struct GardenRegion {
+    plant: char,
     region: HashSet<Point>,
     corners: u32,
 }
@@ -44,6 +48,30 @@ struct GardenPrice {
     corners: u32,
 }
 
+/// One region of the garden, with the measurements the puzzle prices it by.
+struct RegionReport {
+    plant: char,
+    area: u32,
+    perimeter: u32,
+    /// Every corner of the region starts a new side, so this is also its number of corners.
+    sides: u32,
+    /// The top left and bottom right corners of the smallest rectangle around the region.
+    bounding_box: (Point, Point),
+    /// The index of the innermost region that encloses this one: a flood fill from the edge of
+    /// the map that avoids that region's plots never reaches this one.
+    enclosed_by: Option<usize>,
+}
+
+impl RegionReport {
+    fn perimeter_price(&self) -> u32 {
+        self.area * self.perimeter
+    }
+
+    fn sides_price(&self) -> u32 {
+        self.area * self.sides
+    }
+}
+
 fn parse(input: &str) -> HashMap<Point, char> {
     let mut map = HashMap::new();
     for (y, row) in input.lines().enumerate() {
@@ -69,7 +97,7 @@ struct GardenSolver {
 
 impl GardenSolver {
     fn new(map: HashMap<Point, char>) -> Self {
-        let points: HashSet<Point> = map.keys().clone().map(|&point| point).collect();
+        let points: HashSet<Point> = map.keys().clone().copied().collect();
         Self {
             map,
             perimeters: HashMap::new(),
@@ -78,7 +106,12 @@ impl GardenSolver {
         }
     }
 
-    fn is_corner(&mut self, point: Point, dir_pair: &[Point; 2], region_plant: char) -> bool {
+    /// Whether the plot has a corner between the two given directions: either both neighbours are
+    /// outside the region (a convex corner) or both are inside and the diagonal is not (a concave
+    /// one). Comparing plants rather than regions is enough, since a diagonal plot of the same
+    /// plant next to a plot of the region is connected to it. Holes need no special handling:
+    /// their edges are counted from the plots around them like any other.
+    fn is_corner(&self, point: Point, dir_pair: &[Point; 2], region_plant: char) -> bool {
         let neighbours = dir_pair.iter().map(|dir| point + *dir);
         let neighbour_plants: Vec<_> = neighbours
             .map(|neighbour| self.map.get(&neighbour))
@@ -103,46 +136,41 @@ impl GardenSolver {
 
     fn is_perimeter(&self, neighbour: Point, region_plant: char) -> bool {
         let neighbour_plant = self.map.get(&neighbour);
-        match neighbour_plant {
-            Some(&plant) if plant == region_plant => false,
-            _ => true,
-        }
+        !matches!(neighbour_plant, Some(&plant) if plant == region_plant)
     }
 
     fn solve_region(&mut self) {
         let region_point = *self.points.iter().next().expect("Not empty");
         let region_plant = *self.map.get(&region_point).expect("Exists");
 
-        let mut region_points = HashSet::new();
-        region_points.insert(region_point);
-        let mut region_points_to_check = vec![region_point];
+        let region_points = search::flood_fill(region_point, |&point| {
+            DIRECTIONS
+                .iter()
+                .map(move |dir_pair| point + dir_pair[0])
+                .filter(|&neighbour| !self.is_perimeter(neighbour, region_plant))
+                .collect::<Vec<_>>()
+        });
 
         let mut corners = 0;
 
-        while !region_points_to_check.is_empty() {
-            let next_point = region_points_to_check.pop().expect("Not empty");
-            self.points.take(&next_point);
+        for &point in region_points.iter() {
+            self.points.remove(&point);
 
             let mut perimeter = 0;
             for dir_pair in DIRECTIONS.iter() {
-                if self.is_corner(next_point, dir_pair, region_plant) {
+                if self.is_corner(point, dir_pair, region_plant) {
                     corners += 1;
                 }
-
-                let neighbour = next_point + dir_pair[0];
-
-                if self.is_perimeter(neighbour, region_plant) {
+                if self.is_perimeter(point + dir_pair[0], region_plant) {
                     perimeter += 1;
-                } else if !region_points.contains(&neighbour) {
-                    region_points.insert(neighbour);
-                    region_points_to_check.push(neighbour);
                 }
             }
 
-            self.perimeters.insert(next_point, perimeter);
+            self.perimeters.insert(point, perimeter);
         }
 
         self.regions.push(GardenRegion {
+            plant: region_plant,
             region: region_points,
             corners,
         });
@@ -155,46 +183,222 @@ impl GardenSolver {
     }
 }
 
+/// The top left plot of a region in reading order.
+fn first_point(region: &GardenRegion) -> Point {
+    *region
+        .region
+        .iter()
+        .min_by_key(|point| (point.y, point.x))
+        .expect("Regions are not empty")
+}
+
+/// The garden with its regions in the order the puzzle lists them: by their first plot in
+/// reading order.
 fn solve_garden(map: HashMap<Point, char>) -> Garden {
     let mut solver = GardenSolver::new(map);
     solver.solve();
+    solver.regions.sort_by_key(|region| {
+        let point = first_point(region);
+        (point.y, point.x)
+    });
     Garden {
         regions: solver.regions,
         perimeters: solver.perimeters,
     }
 }
 
-fn calculate_price(garden: Garden) -> GardenPrice {
-    let mut perimeter_price = 0;
-    let mut corner_price = 0;
+fn region_reports(garden: &Garden) -> Vec<RegionReport> {
+    let region_of: HashMap<Point, usize> = garden
+        .regions
+        .iter()
+        .enumerate()
+        .flat_map(|(index, region)| region.region.iter().map(move |&point| (point, index)))
+        .collect();
+
+    let mut reports: Vec<RegionReport> = garden
+        .regions
+        .iter()
+        .map(|garden_region| {
+            let area: u32 = garden_region
+                .region
+                .len()
+                .try_into()
+                .expect("Fits into u32");
+
+            let perimeter = garden_region
+                .region
+                .iter()
+                .map(|point| garden.perimeters.get(point).expect("Exists"))
+                .sum::<u32>();
+
+            let (min, max) = garden_region.region.iter().fold(
+                (first_point(garden_region), first_point(garden_region)),
+                |(min, max), point| {
+                    (
+                        Point {
+                            x: min.x.min(point.x),
+                            y: min.y.min(point.y),
+                        },
+                        Point {
+                            x: max.x.max(point.x),
+                            y: max.y.max(point.y),
+                        },
+                    )
+                },
+            );
 
-    for garden_region in garden.regions.iter() {
-        let area: u32 = garden_region
-            .region
-            .len()
-            .try_into()
-            .expect("Fits into u32");
+            RegionReport {
+                plant: garden_region.plant,
+                area,
+                perimeter,
+                sides: garden_region.corners,
+                bounding_box: (min, max),
+                enclosed_by: None,
+            }
+        })
+        .collect();
+
+    // Regions enclosing the same region are nested inside each other, so the innermost one has
+    // the smallest bounding box.
+    let box_area = |report: &RegionReport| {
+        let (min, max) = report.bounding_box;
+        (max.x - min.x + 1) * (max.y - min.y + 1)
+    };
+    for outer in 0..reports.len() {
+        // A region needs a plot on every side of a hole to enclose it.
+        let (min, max) = reports[outer].bounding_box;
+        if max.x - min.x < 2 || max.y - min.y < 2 {
+            continue;
+        }
+        for inner in enclosed_regions(outer, reports[outer].bounding_box, &region_of) {
+            let innermost = reports[inner]
+                .enclosed_by
+                .is_none_or(|current| box_area(&reports[outer]) < box_area(&reports[current]));
+            if innermost {
+                reports[inner].enclosed_by = Some(outer);
+            }
+        }
+    }
+    reports
+}
 
-        let perimeter = garden_region
-            .region
+/// The regions that a flood fill from the edge of the map never reaches when it avoids the plots
+/// of region `outer`. Everything outside the bounding box of `outer` is reachable around it, so
+/// the fill only has to start from a ring of plots just outside the box, reached from `None`.
+fn enclosed_regions(
+    outer: usize,
+    (min, max): (Point, Point),
+    region_of: &HashMap<Point, usize>,
+) -> HashSet<usize> {
+    let (min, max) = (min + Point { x: -1, y: -1 }, max + Point { x: 1, y: 1 });
+    let in_box = move |point: &Point| {
+        (min.x..=max.x).contains(&point.x) && (min.y..=max.y).contains(&point.y)
+    };
+    let plots =
+        move || (min.y..=max.y).flat_map(move |y| (min.x..=max.x).map(move |x| Point { x, y }));
+
+    let reached = search::flood_fill(None, |node| match *node {
+        None => plots()
+            .filter(|point| {
+                point.x == min.x || point.x == max.x || point.y == min.y || point.y == max.y
+            })
+            .map(Some)
+            .collect::<Vec<_>>(),
+        Some(point) => DIRECTIONS
             .iter()
-            .map(|point| garden.perimeters.get(point).expect("Exists"))
-            .sum::<u32>();
+            .map(move |dir_pair| point + dir_pair[0])
+            .filter(|neighbour| in_box(neighbour) && region_of.get(neighbour) != Some(&outer))
+            .map(Some)
+            .collect(),
+    });
+    plots()
+        .filter(|&point| !reached.contains(&Some(point)))
+        .filter_map(|point| region_of.get(&point).copied())
+        .filter(|&index| index != outer)
+        .collect()
+}
 
-        perimeter_price += area * perimeter;
-        corner_price += area * garden_region.corners;
+fn solve(input: &str) -> GardenPrice {
+    let map = parse(input);
+    let garden = solve_garden(map);
+    let reports = region_reports(&garden);
+    GardenPrice {
+        perimeter: reports.iter().map(RegionReport::perimeter_price).sum(),
+        corners: reports.iter().map(RegionReport::sides_price).sum(),
     }
+}
 
-    GardenPrice {
-        perimeter: perimeter_price,
-        corners: corner_price,
+/// Lists every region with its prices, as in the puzzle text.
+fn explain(input: &str) {
+    let reports = region_reports(&solve_garden(parse(input)));
+    println!("Regions:");
+    for (index, report) in reports.iter().enumerate() {
+        let (min, max) = report.bounding_box;
+        print!(
+            "  {index}: {} plants from ({}, {}) to ({}, {}) with {} sides",
+            report.plant, min.x, min.y, max.x, max.y, report.sides
+        );
+        match report.enclosed_by {
+            Some(outer) => println!(", enclosed by region {outer} of {}", reports[outer].plant),
+            None => println!(),
+        }
+    }
+    for (part, measure, price) in [
+        (
+            1,
+            (|report| report.perimeter) as fn(&RegionReport) -> u32,
+            RegionReport::perimeter_price as fn(&RegionReport) -> u32,
+        ),
+        (2, |report| report.sides, RegionReport::sides_price),
+    ] {
+        println!("Part {part}:");
+        for report in &reports {
+            println!(
+                "  A region of {} plants with price {} * {} = {}.",
+                report.plant,
+                report.area,
+                measure(report),
+                price(report)
+            );
+        }
     }
 }
 
-fn solve(input: &str) -> GardenPrice {
+/// Exports the garden with every region in its own colour.
+fn visualize(input: &str) {
+    let Some(export) = FrameExport::from_args() else {
+        eprintln!("Day 12 has no animation; pass --frames <dir> to export an image of its regions");
+        return;
+    };
     let map = parse(input);
+    let (width, height) = map.keys().fold((0, 0), |(w, h), point| {
+        (w.max(point.x as usize + 1), h.max(point.y as usize + 1))
+    });
     let garden = solve_garden(map);
-    calculate_price(garden)
+    let region_of: HashMap<Point, usize> = garden
+        .regions
+        .iter()
+        .enumerate()
+        .flat_map(|(index, region)| region.region.iter().map(move |&point| (point, index)))
+        .collect();
+
+    let image = Image::from_cells(width, height, export.cell_size, |(x, y)| {
+        let point = Point {
+            x: x as i32,
+            y: y as i32,
+        };
+        region_of
+            .get(&point)
+            .map_or(Rgb::BLACK, |&index| Rgb::distinct(index))
+    });
+    let path = export
+        .save("regions", &image)
+        .expect("Should be able to write image");
+    println!(
+        "Wrote {} regions to {}",
+        garden.regions.len(),
+        path.display()
+    );
 }
 
 pub fn part_one(input: &str) -> Option<u32> {
@@ -220,4 +424,96 @@ mod tests {
         let result = part_two(&advent_of_code::template::read_file("examples", DAY));
         assert_eq!(result, Some(1206));
     }
+
+    #[test]
+    fn test_small_example() {
+        let input = advent_of_code::template::read_file_part("examples", DAY, 1);
+        assert_eq!(part_one(&input), Some(140));
+        assert_eq!(part_two(&input), Some(80));
+    }
+
+    #[test]
+    fn test_nested_example() {
+        let input = advent_of_code::template::read_file_part("examples", DAY, 2);
+        assert_eq!(part_one(&input), Some(772));
+        assert_eq!(part_two(&input), Some(436));
+    }
+
+    #[test]
+    fn test_e_shaped_example() {
+        let input = advent_of_code::template::read_file_part("examples", DAY, 3);
+        assert_eq!(part_one(&input), Some(692));
+        assert_eq!(part_two(&input), Some(236));
+    }
+
+    #[test]
+    fn test_diagonal_example() {
+        let input = advent_of_code::template::read_file_part("examples", DAY, 4);
+        assert_eq!(part_one(&input), Some(1184));
+        assert_eq!(part_two(&input), Some(368));
+
+        // The A region surrounds both B regions, which touch only diagonally, so it has the sides
+        // of its outer edge and of both holes.
+        let reports = region_reports(&solve_garden(parse(&input)));
+        assert_eq!(reports.len(), 3);
+        assert_eq!((reports[0].plant, reports[0].sides), ('A', 12));
+        for report in &reports[1..] {
+            assert_eq!((report.plant, report.sides), ('B', 4));
+            assert_eq!(report.enclosed_by, Some(0));
+        }
+    }
+
+    #[test]
+    fn test_region_reports() {
+        let garden = solve_garden(parse(&advent_of_code::template::read_file("examples", DAY)));
+        let reports = region_reports(&garden);
+        let plants: String = reports.iter().map(|report| report.plant).collect();
+        assert_eq!(plants, "RICFVJCEIMS");
+
+        let r = &reports[0];
+        assert_eq!((r.area, r.perimeter, r.sides), (12, 18, 10));
+        assert_eq!((r.perimeter_price(), r.sides_price()), (216, 120));
+        assert_eq!(r.bounding_box, (Point { x: 0, y: 0 }, Point { x: 4, y: 3 }));
+        assert_eq!(r.enclosed_by, None);
+
+        assert!(reports.iter().all(|report| report.enclosed_by.is_none()));
+    }
+
+    #[test]
+    fn test_enclosed_regions() {
+        let garden = solve_garden(parse(&advent_of_code::template::read_file_part(
+            "examples", DAY, 2,
+        )));
+        let reports = region_reports(&garden);
+        assert_eq!(reports.len(), 5);
+        assert_eq!(reports[0].plant, 'O');
+        assert_eq!(reports[0].enclosed_by, None);
+        assert_eq!(
+            reports[0].bounding_box,
+            (Point { x: 0, y: 0 }, Point { x: 4, y: 4 })
+        );
+        for report in &reports[1..] {
+            assert_eq!((report.plant, report.enclosed_by), ('X', Some(0)));
+        }
+    }
+
+    #[test]
+    fn test_nested_regions() {
+        let reports = region_reports(&solve_garden(parse("AAAAA\nABBBA\nABCBA\nABBBA\nAAAAA\n")));
+        let enclosures: Vec<_> = reports
+            .iter()
+            .map(|report| (report.plant, report.enclosed_by))
+            .collect();
+        assert_eq!(enclosures, [('A', None), ('B', Some(0)), ('C', Some(1))]);
+    }
+
+    #[test]
+    fn test_regions_sharing_a_hole() {
+        let reports = region_reports(&solve_garden(parse("AAAAAA\nABBCCA\nABBCCA\nAAAAAA\n")));
+        let enclosures: Vec<_> = reports
+            .iter()
+            .map(|report| (report.plant, report.enclosed_by))
+            .collect();
+        assert_eq!(enclosures, [('A', None), ('B', Some(0)), ('C', Some(0))]);
+    }
 }
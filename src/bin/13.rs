@@ -1,4 +1,5 @@
-use num_rational::Rational32;
+use advent_of_code::linear::{self, Solution};
+use advent_of_code::math::extended_gcd;
 use regex::Regex;
 
 advent_of_code::solution!(13);
@@ -11,6 +12,9 @@ const MACHINE_PATTERN: &str = concat!(
 
 const PRIZE_TRANSLATION: i64 = 10000000000000;
 
+const A_COST: i128 = 3;
+const B_COST: i128 = 1;
+
 struct Point {
     x: i64,
     y: i64,
@@ -55,6 +59,37 @@ fn parse(input: &str, prize_translation: i64) -> Vec<Machine> {
         .collect()
 }
 
+/// The cheapest way to reach `target` along a single axis, where a press of button A moves by
+/// `a_step` and a press of button B by `b_step`.
+///
+/// Solutions of a_step * a + b_step * b = target are spaced evenly along the line:
+/// a = a0 + k * b_step / g
+/// b = b0 - k * a_step / g
+/// where (a0, b0) is one solution from the extended Euclidean algorithm and g = gcd(a_step, b_step).
+/// The cost 3a + b changes linearly with k, so the cheapest solution sits at one end of the range
+/// of k for which both a and b are non-negative.
+fn cheapest_on_line(a_step: i128, b_step: i128, target: i128) -> Option<(i128, i128)> {
+    if a_step == 0 && b_step == 0 {
+        return (target == 0).then_some((0, 0));
+    }
+    let (g, x, y) = extended_gcd(a_step, b_step);
+    if target % g != 0 {
+        return None;
+    }
+    let (a0, b0) = (x * target / g, y * target / g);
+    let (a_spacing, b_spacing) = (b_step / g, a_step / g);
+
+    let k = if A_COST * a_spacing - B_COST * b_spacing >= 0 {
+        // cheapest with as few presses of A as possible: smallest k with a >= 0
+        -a0.div_euclid(a_spacing)
+    } else {
+        // cheapest with as few presses of B as possible: largest k with b >= 0
+        b0.div_euclid(b_spacing)
+    };
+    let (a, b) = (a0 + k * a_spacing, b0 - k * b_spacing);
+    (a >= 0 && b >= 0).then_some((a, b))
+}
+
 /// Calculate the cost of pressing buttons to win the prize for each machine
 ///
 /// Given the following machine:
@@ -67,71 +102,44 @@ fn parse(input: &str, prize_translation: i64) -> Vec<Machine> {
 /// 34a + 67b = 5400
 /// which is just a system of linear equations.
 ///
-/// Firstly we can check the gradients of the two lines are not parallel.
-/// If we rewrite both equations in the form y = mx + c:
-/// a = (8400 - 22b) / 94
-/// a = 8400/94 - 22/94 * b
-///
-/// a = (5400 - 67b) / 34
-/// a = 5400/34 - 67/34 * b
-///
-/// 22/94 != 67/34; hence the lines are not parallel.
-///
-/// We can then solve the system of equations using the substitution method.
-/// First, we can rearrange the first equation to solve for b:
-/// 22b = 8400 - 94a
-/// b = (8400 - 94a) / 22
-///
-/// We can then substitute this value of b into the second equation:
-/// 34a + 67(8400/22 - 94/22 * a) = 5400
-/// 34a + 67(8400/22) - 67(94/22) * a = 5400
-/// 34a - 67(94/22) * a = 5400 - 67(8400/22)
-/// 22 * 34a - 67 * 94a = 22 * 5400 - 67 * 8400
-/// a = (22 * 5400 - 67 * 8400) / (22 * 34 - 67 * 94)
-///
-/// This means that a = (b_x * prize_y - b_y * prize_x) / (b_x * a_y - a_x * b_y)
+/// If it has a unique solution, the machine can only be won if that solution is a pair of
+/// non-negative integers. If both equations describe the same line, every point on it wins the
+/// prize and we pick the cheapest one.
 ///
-/// We can then substitute this value of a back into the first equation to solve for b:
-/// b = (8400 - 94a) / 22
-///
-/// This means that b = (prize_x - a_x * a) / b_x
-///
-/// Once we have the values of a and b, we can calculate the cost of pressing the buttons
-/// which is just 3 * a + b
+/// The cost of pressing the buttons is 3 * a + b
 fn calc_costs(machines: Vec<Machine>) -> u64 {
     machines
         .into_iter()
         .filter_map(|machine| {
-            let machine_a_x_i32: i32 = machine.a.x.try_into().expect("Fits into i32");
-            let machine_b_x_i32: i32 = machine.b.x.try_into().expect("Fits into i32");
-            let machine_a_y_i32: i32 = machine.a.y.try_into().expect("Fits into i32");
-            let machine_b_y_i32: i32 = machine.b.y.try_into().expect("Fits into i32");
-            let m_x = Rational32::new(-machine_a_x_i32, machine_b_x_i32);
-            let m_y = Rational32::new(-machine_a_y_i32, machine_b_y_i32);
-            if m_x == m_y {
-                panic!("Lines are parallel");
-            }
-
-            let a_numer = machine.b.x * machine.prize.y - machine.b.y * machine.prize.x;
-            let a_denom = machine.b.x * machine.a.y - machine.a.x * machine.b.y;
-            if a_numer % a_denom != 0 {
-                // No solution for this machine
+            let matrix = vec![
+                vec![machine.a.x.into(), machine.b.x.into()],
+                vec![machine.a.y.into(), machine.b.y.into()],
+            ];
+            let rhs = [machine.prize.x.into(), machine.prize.y.into()];
+            let solution = linear::solve(&matrix, &rhs);
+            let (a, b) = match solution {
+                Solution::Unique(_) => {
+                    let presses = solution.integers()?;
+                    (presses[0], presses[1])
+                }
+                Solution::None => return None::<u64>,
+                Solution::Infinite => {
+                    // both equations are multiples of the same one; use one that is not 0 = 0
+                    let (axis, prize) = if machine.a.x != 0 || machine.b.x != 0 {
+                        (0, machine.prize.x)
+                    } else {
+                        (1, machine.prize.y)
+                    };
+                    cheapest_on_line(matrix[axis][0], matrix[axis][1], prize.into())?
+                }
+            };
+            if a < 0 || b < 0 {
+                // buttons cannot be pressed a negative number of times
                 return None;
             }
-            let a = a_numer / a_denom;
 
-            let b_numer = machine.prize.x - machine.a.x * a;
-            let b_denom = machine.b.x;
-            if b_numer % b_denom != 0 {
-                // No solution for this machine
-                return None;
-            }
-            let b = b_numer / b_denom;
-
-            let a: u64 = a.try_into().expect("Fits into u64");
-            let b: u64 = b.try_into().expect("Fits into u64");
-
-            Some(3 * a + b)
+            let cost = A_COST * a + B_COST * b;
+            Some(cost.try_into().expect("Fits into u64"))
         })
         .sum()
 }
@@ -158,4 +166,26 @@ mod tests {
         let result = part_one(&advent_of_code::template::read_file("examples", DAY));
         assert_eq!(result, Some(480));
     }
+
+    #[test]
+    fn test_parallel_buttons() {
+        // B is cheaper per step along X, so as many presses of B as possible
+        let input = "Button A: X+2, Y+4\nButton B: X+3, Y+6\nPrize: X=12, Y=24";
+        assert_eq!(part_one(input), Some(4));
+        // A covers three times the distance of B for three times the cost: any mix is equally cheap
+        let input = "Button A: X+6, Y+3\nButton B: X+2, Y+1\nPrize: X=10, Y=5";
+        assert_eq!(part_one(input), Some(5));
+        // A covers more than three times the distance of B
+        let input = "Button A: X+7, Y+14\nButton B: X+2, Y+4\nPrize: X=16, Y=32";
+        assert_eq!(part_one(input), Some(7));
+        // on the line, but unreachable with whole presses
+        let input = "Button A: X+2, Y+4\nButton B: X+4, Y+8\nPrize: X=3, Y=6";
+        assert_eq!(part_one(input), Some(0));
+        // on the line, but only reachable with a negative number of presses
+        let input = "Button A: X+4, Y+2\nButton B: X+6, Y+3\nPrize: X=2, Y=1";
+        assert_eq!(part_one(input), Some(0));
+        // off the line
+        let input = "Button A: X+4, Y+2\nButton B: X+6, Y+3\nPrize: X=10, Y=6";
+        assert_eq!(part_one(input), Some(0));
+    }
 }
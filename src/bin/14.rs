@@ -1,18 +1,68 @@
 use std::{
     collections::HashMap,
+    env,
     ops::{Add, Mul},
+    str::FromStr,
 };
 
-use num_bigint::BigUint;
+use advent_of_code::grid::Render;
+use advent_of_code::image::{Palette, Rgb};
+use advent_of_code::math::{self, Congruence};
+use advent_of_code::simulation::{self, FrameExport, Simulation};
 use regex::Regex;
 
-advent_of_code::solution!(14);
+advent_of_code::solution!(14; visualize = visualize);
 
-const EXAMPLE_INPUT_LINES: u32 = 12;
-const EXAMPLE_WIDTH: u32 = 11;
-const EXAMPLE_HEIGHT: u32 = 7;
-const REAL_WIDTH: u32 = 101;
-const REAL_HEIGHT: u32 = 103;
+const REAL_BATHROOM: Bathroom = Bathroom {
+    width: 101,
+    height: 103,
+};
+
+/// Overrides the size of the bathroom when `--size` is not given.
+const SIZE_VARIABLE: &str = "AOC_BATHROOM_SIZE";
+
+/// The size of the grid the robots move on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Bathroom {
+    width: u32,
+    height: u32,
+}
+
+impl Bathroom {
+    /// The size given as `--size <width>x<height>` or in `AOC_BATHROOM_SIZE`, falling back to the
+    /// 101x103 of the puzzle.
+    fn from_args() -> Self {
+        let args: Vec<String> = env::args().collect();
+        simulation::arg_value(&args, "--size")
+            .or_else(|| {
+                let value = env::var(SIZE_VARIABLE).ok()?;
+                let parsed = value.parse().ok();
+                if parsed.is_none() {
+                    eprintln!("Ignoring invalid value for {SIZE_VARIABLE}: {value}");
+                }
+                parsed
+            })
+            .unwrap_or(REAL_BATHROOM)
+    }
+}
+
+impl FromStr for Bathroom {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (width, height) = s
+            .split_once('x')
+            .ok_or_else(|| format!("Expected <width>x<height>, got {s}"))?;
+        let size = |value: &str| match value.parse() {
+            Ok(0) | Err(_) => Err(format!("Expected a positive size, got {value}")),
+            Ok(size) => Ok(size),
+        };
+        Ok(Self {
+            width: size(width)?,
+            height: size(height)?,
+        })
+    }
+}
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 struct Point {
@@ -57,10 +107,12 @@ impl Mul<i32> for Point {
     }
 }
 
-fn parse(lines: Vec<&str>) -> Vec<Vector> {
+/// Parses one robot per line, skipping blank lines.
+fn parse(input: &str) -> Vec<Vector> {
     let re = Regex::new(r"-?\d+").expect("Regex pattern should be valid");
-    lines
-        .into_iter()
+    input
+        .lines()
+        .filter(|line| !line.trim().is_empty())
         .map(|line| {
             let mut nums = re
                 .find_iter(line)
@@ -78,181 +130,304 @@ fn parse(lines: Vec<&str>) -> Vec<Vector> {
         .collect()
 }
 
-fn advance(vectors: &Vec<Vector>, time: u32, width: u32, height: u32) -> Vec<Point> {
+fn advance(vectors: &[Vector], time: u32, bathroom: Bathroom) -> Vec<Point> {
+    let width: i32 = bathroom
+        .width
+        .try_into()
+        .expect("Width should fit into i32");
+    let height: i32 = bathroom
+        .height
+        .try_into()
+        .expect("Height should fit into i32");
     vectors
         .iter()
         .map(|vector| {
             let unmod_pos =
                 vector.pos + vector.vel * time.try_into().expect("Time should fit into i32");
             Point {
-                x: unmod_pos
-                    .x
-                    .rem_euclid(width.try_into().expect("Width should fit into i32")),
-                y: unmod_pos
-                    .y
-                    .rem_euclid(height.try_into().expect("Height should fit into i32")),
+                x: unmod_pos.x.rem_euclid(width),
+                y: unmod_pos.y.rem_euclid(height),
             }
         })
         .collect()
 }
 
-fn find_cycles(vectors: &Vec<Vector>, width: u32, height: u32) -> Option<Cycle2D> {
-    let mut time: u32 = 0;
-    let mut x_time_pattern = vec![];
-    let mut y_time_pattern = vec![];
-    loop {
-        time += 1;
-        let advanced_points = advance(&vectors, time, width, height);
-        if time > width * height {
-            return None;
-        }
-
-        if x_time_pattern.len() == 3 && y_time_pattern.len() == 3 {
-            break;
-        }
-
-        let mut x_points = HashMap::new();
-        for point in advanced_points.iter() {
-            let count = x_points.entry(point.x).or_insert(0);
-            *count += 1;
-        }
+/// Position of a robot along an axis of length `size` after `time` seconds.
+fn axis_position(position: i32, velocity: i32, time: u32, size: u32) -> i64 {
+    (i64::from(position) + i64::from(velocity) * i64::from(time)).rem_euclid(i64::from(size))
+}
 
-        let mut y_points = HashMap::new();
-        for point in advanced_points.iter() {
-            let count = y_points.entry(point.y).or_insert(0);
-            *count += 1;
-        }
+/// Finds when the robots are most tightly clustered along one axis.
+///
+/// A robot's coordinate repeats every `size / gcd(velocity, size)` seconds, so the coordinates of
+/// all robots repeat after the lcm of those periods. Within one period the tree shows up as the
+/// time with the smallest variance, as most robots are packed into its frame.
+fn find_axis_cycle(vectors: &[Vector], size: u32, axis: fn(&Point) -> i32) -> Cycle {
+    let period = math::lcm_all(vectors.iter().map(|vector| {
+        let velocity = axis(&vector.vel).rem_euclid(size as i32) as u32;
+        size / math::gcd(velocity, size)
+    }));
+    let offset = (0..period)
+        .min_by_key(|&time| {
+            let (n, sum, sum_of_squares) = vectors
+                .iter()
+                .map(|vector| axis_position(axis(&vector.pos), axis(&vector.vel), time, size))
+                .fold((0, 0, 0), |(n, sum, squares), c| {
+                    (n + 1, sum + c, squares + c * c)
+                });
+            // n² times the variance, which keeps the comparison in integers
+            n * sum_of_squares - sum * sum
+        })
+        .expect("Period should be at least 1");
+    Cycle { offset, period }
+}
 
-        // apply heuristics to find cycles
-        if advanced_points
-            .iter()
-            .any(|point| x_points[&point.x] > width / 4)
-        {
-            x_time_pattern.push(time);
-        }
+fn find_cycles(vectors: &[Vector], bathroom: Bathroom) -> Cycle2D {
+    Cycle2D {
+        x: find_axis_cycle(vectors, bathroom.width, |point| point.x),
+        y: find_axis_cycle(vectors, bathroom.height, |point| point.y),
+    }
+}
 
-        if advanced_points
-            .iter()
-            .any(|point| y_points[&point.y] > height / 4)
-        {
-            y_time_pattern.push(time);
-        }
+/// Draws the number of robots on each tile, or `.` for empty tiles.
+fn render(points: &[Point], bathroom: Bathroom) -> String {
+    let mut counts = HashMap::new();
+    for point in points {
+        *counts
+            .entry((point.x as usize, point.y as usize))
+            .or_insert(0) += 1;
     }
-    assert_eq!(
-        x_time_pattern[2] - x_time_pattern[1],
-        x_time_pattern[1] - x_time_pattern[0]
-    );
-    assert_eq!(
-        y_time_pattern[2] - y_time_pattern[1],
-        y_time_pattern[1] - y_time_pattern[0]
-    );
-
-    Some(Cycle2D {
-        x: Cycle {
-            offset: x_time_pattern[0],
-            period: x_time_pattern[1] - x_time_pattern[0],
-        },
-        y: Cycle {
-            offset: y_time_pattern[0],
-            period: y_time_pattern[1] - y_time_pattern[0],
-        },
-    })
+    let (width, height) = (bathroom.width as usize, bathroom.height as usize);
+    let render = Render::new(width, height, |position| match counts.get(&position) {
+        Some(&count) if count < 10 => char::from_digit(count, 10).expect("Count is a digit"),
+        Some(_) => '*',
+        None => '.',
+    });
+    render.to_string()
 }
 
-// Using Chinese Remainder Theorem, we can find the time when the periods are aligned
-// Let
+// The tree appears once both axes are clustered, i.e. at the time n with
 // n === x_offset mod x_period
 // n === y_offset mod y_period
-//
-// Then we can rearrange the y cycle to get
-// n = y_period * j + y_offset (for some integer j)
-//
-// Substituting this into the x cycle, we get
-// y_period * j + y_offset === x_offset mod x_period
-//
-// Rearranging this, we get
-// y_period * j === (x_offset - y_offset) mod x_period
-// (y_period % x_period) j === (x_offset - y_offset) mod x_period
-
-// We can find the modular inverse of y_period % x_period to remove it from the left side
-// j === (x_offset - y_offset) * (mod_inv (y_period % x_period) mod=x_period)) mod x_period
-//
-// Which enables use to find j using modulo operator
-// j = (x_offset - y_offset) * (mod_inv (y_period % x_period) mod=x_period)) % x_period
-//
-// Substituting this back into the y cycle, we get
-// n = y_period * ((x_offset - y_offset) * (mod_inv (y_period % x_period) mod=x_period)) % x_period) + y_offset
-fn crt(cycle: Cycle2D) -> u32 {
-    let base = BigUint::from(cycle.y.period % cycle.x.period);
-    let mod_val = BigUint::from(cycle.x.period);
-    let mod_inv = base.modinv(&mod_val).expect("Modular inverse should exist");
-    let result = cycle.y.period * ((cycle.x.offset - cycle.y.offset) * mod_inv % cycle.x.period)
-        + cycle.y.offset;
-    result.try_into().expect("Result should fit into u32")
+// which the Chinese Remainder Theorem solves. There is no such time if the periods share a factor
+// and the offsets disagree on it.
+fn crt(cycle: Cycle2D) -> Option<u32> {
+    let congruence = |axis: Cycle| Congruence::new(axis.offset.into(), axis.period.into());
+    let solution = math::crt([congruence(cycle.x), congruence(cycle.y)])?;
+    Some(
+        solution
+            .residue
+            .try_into()
+            .expect("Result should fit into u32"),
+    )
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let time = 100;
-    let lines = input.lines().collect::<Vec<_>>();
-    let width: u32;
-    let height: u32;
-    if lines.len()
-        == EXAMPLE_INPUT_LINES
-            .try_into()
-            .expect("Should fit into usize")
-    {
-        // example input
-        width = EXAMPLE_WIDTH;
-        height = EXAMPLE_HEIGHT;
-    } else {
-        // real input
-        width = REAL_WIDTH;
-        height = REAL_HEIGHT;
+/// The first time the robots form the tree.
+fn find_tree(vectors: &[Vector], bathroom: Bathroom) -> Option<u32> {
+    crt(find_cycles(vectors, bathroom))
+}
+
+/// The robots moving one second at a time until they form the tree, for `--visualize`.
+struct Robots {
+    vectors: Vec<Vector>,
+    bathroom: Bathroom,
+    time: u32,
+    tree: u32,
+}
+
+impl Simulation for Robots {
+    fn step(&mut self) {
+        self.time += 1;
     }
-    let vectors = parse(lines);
-    let advanced_points = advance(&vectors, time, width, height);
 
-    let quadrants = {
-        let width_mid = ((width - 1) / 2)
-            .try_into()
-            .expect("Width should fit into i32");
-        let height_mid = ((height - 1) / 2)
-            .try_into()
-            .expect("Height should fit into i32");
-        let mut quadrants = HashMap::new();
-        for point in advanced_points {
-            if point.x == width_mid || point.y == height_mid {
-                continue;
-            }
-            let count = quadrants
-                .entry((point.x < width_mid, point.y < height_mid))
-                .or_insert(0);
-            *count += 1;
-        }
-        quadrants
+    fn render(&self) -> String {
+        render(
+            &advance(&self.vectors, self.time, self.bathroom),
+            self.bathroom,
+        )
+    }
+
+    fn is_done(&self) -> bool {
+        self.time >= self.tree
+    }
+}
+
+fn visualize(input: &str) {
+    let vectors = parse(input);
+    let bathroom = Bathroom::from_args();
+    let tree = find_tree(&vectors, bathroom).expect("Robots should form a tree");
+    let mut robots = Robots {
+        vectors,
+        bathroom,
+        time: 0,
+        tree,
     };
+    let palette = ('1'..='9')
+        .chain(['*'])
+        .fold(Palette::default(), |palette, glyph| {
+            palette.with(glyph, Rgb(64, 200, 64))
+        });
+
+    match FrameExport::from_args() {
+        // just the tree, unless --jump asks for the frames leading up to it
+        Some(export) if export.skip == 0 => {
+            robots.time = robots.tree;
+            let image = export.image(&robots.render(), &palette);
+            let path = export
+                .save("tree", &image)
+                .expect("Should be able to write image");
+            println!(
+                "Wrote the tree at {} seconds to {}",
+                robots.tree,
+                path.display()
+            );
+        }
+        _ => simulation::visualize(&mut robots, &palette),
+    }
+}
+
+/// Product of the number of robots in each quadrant after `time` seconds. Robots exactly on a
+/// middle row or column do not count.
+fn safety_factor(vectors: &[Vector], time: u32, bathroom: Bathroom) -> u32 {
+    let width_mid: i32 = ((bathroom.width - 1) / 2)
+        .try_into()
+        .expect("Width should fit into i32");
+    let height_mid: i32 = ((bathroom.height - 1) / 2)
+        .try_into()
+        .expect("Height should fit into i32");
+    // an empty quadrant makes the product 0, so count all four even if no robot is in them
+    let mut quadrants = [0; 4];
+    for point in advance(vectors, time, bathroom) {
+        if point.x == width_mid || point.y == height_mid {
+            continue;
+        }
+        let quadrant = usize::from(point.x < width_mid) * 2 + usize::from(point.y < height_mid);
+        quadrants[quadrant] += 1;
+    }
+    quadrants.iter().product()
+}
+
+fn solve_part_one(input: &str, bathroom: Bathroom) -> u32 {
+    safety_factor(&parse(input), 100, bathroom)
+}
 
-    Some(quadrants.values().product())
+fn solve_part_two(input: &str, bathroom: Bathroom) -> Option<u32> {
+    find_tree(&parse(input), bathroom)
+}
+
+pub fn part_one(input: &str) -> Option<u32> {
+    Some(solve_part_one(input, Bathroom::from_args()))
 }
 
 pub fn part_two(input: &str) -> Option<u32> {
-    // part_two can only be used on the real input
-    let lines = input.lines().collect::<Vec<_>>();
-    let width = REAL_WIDTH;
-    let height = REAL_HEIGHT;
-    let vectors = parse(lines);
-
-    let cycle = find_cycles(&vectors, width, height).expect("Cycle should be found");
-    Some(crt(cycle))
+    solve_part_two(input, Bathroom::from_args())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const EXAMPLE_BATHROOM: Bathroom = Bathroom {
+        width: 11,
+        height: 7,
+    };
+
     #[test]
     fn test_part_one() {
-        let result = part_one(&advent_of_code::template::read_file("examples", DAY));
-        assert_eq!(result, Some(12));
+        let input = advent_of_code::template::read_file("examples", DAY);
+        assert_eq!(solve_part_one(&input, EXAMPLE_BATHROOM), 12);
+    }
+
+    #[test]
+    fn test_part_two_example_structure() {
+        let input = advent_of_code::template::read_file("examples", DAY);
+        let vectors = parse(&input);
+        assert_eq!(vectors.len(), 12);
+
+        // every robot moves on both axes, so positions repeat after exactly width x height seconds
+        let cycles = find_cycles(&vectors, EXAMPLE_BATHROOM);
+        assert_eq!((cycles.x.period, cycles.y.period), (11, 7));
+        assert_eq!(
+            advance(&vectors, 77, EXAMPLE_BATHROOM),
+            advance(&vectors, 0, EXAMPLE_BATHROOM)
+        );
+
+        // the example has no tree, but the most clustered time is still well defined
+        assert_eq!((cycles.x.offset, cycles.y.offset), (2, 3));
+        assert_eq!(solve_part_two(&input, EXAMPLE_BATHROOM), Some(24));
+    }
+
+    #[test]
+    fn test_parse_bathroom() {
+        assert_eq!("11x7".parse(), Ok(EXAMPLE_BATHROOM));
+        assert_eq!("101x103".parse(), Ok(REAL_BATHROOM));
+        assert!("11".parse::<Bathroom>().is_err());
+        assert!("0x7".parse::<Bathroom>().is_err());
+        assert!("11x-7".parse::<Bathroom>().is_err());
+    }
+
+    #[test]
+    fn test_parse_any_number_of_robots() {
+        assert!(parse("").is_empty());
+        let input = "p=0,4 v=3,-3\n\np=6,3 v=-1,-3\n";
+        let vectors = parse(input);
+        assert_eq!(vectors.len(), 2);
+        assert_eq!(solve_part_one(input, EXAMPLE_BATHROOM), 0);
+    }
+
+    #[test]
+    fn test_find_tree_on_custom_grid() {
+        let bathroom = Bathroom {
+            width: 31,
+            height: 37,
+        };
+        let tree_time = 500;
+        // 45 robots meet in a 3x3 block at the tree time, 10 are scattered across the grid
+        let vectors: Vec<Vector> = (0..55)
+            .map(|i: i32| {
+                let target = if i < 45 {
+                    Point {
+                        x: 14 + i % 3,
+                        y: 17 + i / 3 % 3,
+                    }
+                } else {
+                    Point {
+                        x: i * 7 % 31,
+                        y: i * 11 % 37,
+                    }
+                };
+                let vel = Point {
+                    x: i % 29 - 14,
+                    y: i % 23 - 11,
+                };
+                let start = target + vel * -tree_time;
+                Vector {
+                    pos: Point {
+                        x: start.x.rem_euclid(31),
+                        y: start.y.rem_euclid(37),
+                    },
+                    vel,
+                }
+            })
+            .collect();
+        assert_eq!(find_tree(&vectors, bathroom), Some(500));
+    }
+
+    #[test]
+    fn test_render() {
+        let input = advent_of_code::template::read_file("examples", DAY);
+        let points = advance(&parse(&input), 100, EXAMPLE_BATHROOM);
+        assert_eq!(
+            render(&points, EXAMPLE_BATHROOM),
+            concat!(
+                "......2..1.\n",
+                "...........\n",
+                "1..........\n",
+                ".11........\n",
+                ".....1.....\n",
+                "...12......\n",
+                ".1....1....",
+            )
+        );
     }
 }
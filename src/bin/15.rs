@@ -1,6 +1,11 @@
+use advent_of_code::grid::{Cell, Render};
+use advent_of_code::image::{Palette, Rgb};
+use advent_of_code::search;
+use advent_of_code::simulation::{self, Simulation};
+use advent_of_code::template::{ANSI_BOLD, ANSI_YELLOW};
 use std::{collections::HashMap, hash::Hash, vec};
 
-advent_of_code::solution!(15);
+advent_of_code::solution!(15; visualize = visualize);
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 struct Point {
@@ -77,7 +82,8 @@ impl Warehouse {
 struct WarehouseSimulator {
     warehouse: Warehouse,
     moves: Vec<Move>,
-    start: Point,
+    robot: Point,
+    next_move: usize,
 }
 
 impl WarehouseSimulator {
@@ -159,7 +165,8 @@ impl WarehouseSimulator {
         Self {
             warehouse: Warehouse { map_ },
             moves,
-            start: start.expect("Start should be in input"),
+            robot: start.expect("Start should be in input"),
+            next_move: 0,
         }
     }
 
@@ -178,73 +185,59 @@ impl WarehouseSimulator {
         }
     }
 
-    fn find_recursive(&self, mut pos: Point, dir: Move) -> Option<Vec<Point>> {
-        let mut q = vec![pos];
-        let mut boxes = vec![];
-        while !q.is_empty() {
-            pos = q.remove(0);
-            let box_side_pos = move_point(pos, dir);
-            match self.warehouse.get(&box_side_pos) {
-                Some(Space::Wall) => return None,
-                Some(space @ (Space::BoxLeft | Space::BoxRight)) => {
-                    let box_move = if *space == Space::BoxLeft {
-                        Move::Right
-                    } else {
-                        Move::Left
-                    };
-
-                    if !q.contains(&box_side_pos) {
-                        q.push(box_side_pos);
-                    }
-                    if !boxes.contains(&box_side_pos) {
-                        boxes.push(box_side_pos);
-                    }
-
-                    let box_other_side_pos = move_point(box_side_pos, box_move);
-                    if !q.contains(&box_other_side_pos) {
-                        q.push(box_other_side_pos);
-                    }
-                    if !boxes.contains(&box_other_side_pos) {
-                        boxes.push(box_other_side_pos);
-                    }
-                }
-                _ => (),
+    /// Finds the wide boxes pushed by a vertical move, nearest first, or `None` if any is blocked.
+    fn find_recursive(&self, pos: Point, dir: Move) -> Option<Vec<Point>> {
+        let pushed = search::bfs([pos], |&pushing_pos| {
+            let box_side_pos = move_point(pushing_pos, dir);
+            let box_move = match self.warehouse.get(&box_side_pos) {
+                Some(Space::BoxLeft) => Move::Right,
+                Some(Space::BoxRight) => Move::Left,
+                _ => return vec![],
             };
+            vec![box_side_pos, move_point(box_side_pos, box_move)]
+        });
+
+        let mut boxes: Vec<Point> = pushed.distances.into_keys().filter(|p| *p != pos).collect();
+        if boxes
+            .iter()
+            .chain([&pos])
+            .any(|p| self.warehouse.get(&move_point(*p, dir)) == Some(&Space::Wall))
+        {
+            return None;
         }
+        boxes.sort_by_key(|p| p.y.abs_diff(pos.y));
         Some(boxes)
     }
 
     fn simulate(&mut self) {
-        let mut pos = self.start;
-        for move_ in self.moves.iter() {
-            let new_pos = move_point(pos, *move_);
-            let new_space = self
-                .warehouse
-                .get(&new_pos)
-                .expect("Will always be in map as map is bounded");
-            match (new_space, move_) {
-                (Space::Wall, _) => continue,
-                (Space::Empty, _) => pos = new_pos,
-                (Space::BoxLeft | Space::BoxRight, Move::Up | Move::Down) => {
-                    let boxes = self.find_recursive(pos, *move_);
-                    match boxes {
-                        Some(boxes) => self.warehouse.shift_boxes(boxes, *move_),
-                        None => continue,
-                    }
-                    pos = new_pos;
-                }
-                (Space::BoxLeft | Space::BoxRight | Space::Box, _) => {
-                    let boxes = self.find(pos, *move_);
-                    match boxes {
-                        Some(boxes) => self.warehouse.shift_boxes(boxes, *move_),
-                        None => continue,
-                    }
-                    pos = new_pos;
-                }
-            }
+        while !self.is_done() {
+            self.step();
         }
     }
 
+    fn render_grid(&self) -> Render<'_> {
+        let (width, height) = self
+            .warehouse
+            .iter()
+            .fold((0, 0), |(w, h), (p, _)| (w.max(p.x + 1), h.max(p.y + 1)));
+        Render::new(width as usize, height as usize, |(x, y)| {
+            let point = Point {
+                x: x as u32,
+                y: y as u32,
+            };
+            if point == self.robot {
+                return Cell::styled('@', ANSI_BOLD);
+            }
+            match self.warehouse.get(&point) {
+                Some(Space::Empty) | None => '.'.into(),
+                Some(Space::Wall) => '#'.into(),
+                Some(Space::Box) => Cell::styled('O', ANSI_YELLOW),
+                Some(Space::BoxLeft) => Cell::styled('[', ANSI_YELLOW),
+                Some(Space::BoxRight) => Cell::styled(']', ANSI_YELLOW),
+            }
+        })
+    }
+
     fn calculate(&self) -> u32 {
         self.warehouse
             .iter()
@@ -256,6 +249,57 @@ impl WarehouseSimulator {
     }
 }
 
+impl Simulation for WarehouseSimulator {
+    /// Applies the next move of the robot.
+    fn step(&mut self) {
+        let move_ = self.moves[self.next_move];
+        self.next_move += 1;
+
+        let pos = self.robot;
+        let new_pos = move_point(pos, move_);
+        let new_space = self
+            .warehouse
+            .get(&new_pos)
+            .expect("Will always be in map as map is bounded");
+        let boxes = match (new_space, move_) {
+            (Space::Wall, _) => return,
+            (Space::Empty, _) => vec![],
+            (Space::BoxLeft | Space::BoxRight, Move::Up | Move::Down) => {
+                match self.find_recursive(pos, move_) {
+                    Some(boxes) => boxes,
+                    None => return,
+                }
+            }
+            (Space::BoxLeft | Space::BoxRight | Space::Box, _) => match self.find(pos, move_) {
+                Some(boxes) => boxes,
+                None => return,
+            },
+        };
+        self.warehouse.shift_boxes(boxes, move_);
+        self.robot = new_pos;
+    }
+
+    fn render(&self) -> String {
+        self.render_grid().coloured().to_string()
+    }
+
+    fn is_done(&self) -> bool {
+        self.next_move == self.moves.len()
+    }
+}
+
+fn visualize(input: &str) {
+    let boxes = Rgb(255, 192, 0);
+    let palette = Palette::default()
+        .with('#', Rgb(128, 128, 128))
+        .with('O', boxes)
+        .with('[', boxes)
+        .with(']', boxes)
+        .with('@', Rgb(255, 64, 64));
+    // the wide warehouse of part two is the more interesting one to watch
+    simulation::visualize(&mut WarehouseSimulator::parse(input, true), &palette);
+}
+
 pub fn part_one(input: &str) -> Option<u32> {
     let mut simulator = WarehouseSimulator::parse(input, false);
     simulator.simulate();
@@ -296,4 +340,43 @@ mod tests {
         assert_eq!(result, Some(9021));
     }
 
+    #[test]
+    fn test_render_final_state() {
+        let input = advent_of_code::template::read_file_part("examples", DAY, 1);
+        let mut simulator = WarehouseSimulator::parse(&input, false);
+        simulator.simulate();
+        assert_eq!(
+            simulator.render_grid().to_string(),
+            concat!(
+                "##########\n",
+                "#.O.O.OOO#\n",
+                "#........#\n",
+                "#OO......#\n",
+                "#OO@.....#\n",
+                "#O#.....O#\n",
+                "#O.....OO#\n",
+                "#O.....OO#\n",
+                "#OO....OO#\n",
+                "##########",
+            )
+        );
+
+        let mut simulator = WarehouseSimulator::parse(&input, true);
+        simulator.simulate();
+        assert_eq!(
+            simulator.render_grid().to_string(),
+            concat!(
+                "####################\n",
+                "##[].......[].[][]##\n",
+                "##[]...........[].##\n",
+                "##[]........[][][]##\n",
+                "##[]......[]....[]##\n",
+                "##..##......[]....##\n",
+                "##..[]............##\n",
+                "##..@......[].[][]##\n",
+                "##......[][]..[]..##\n",
+                "####################",
+            )
+        );
+    }
 }
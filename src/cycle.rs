@@ -0,0 +1,187 @@
+//! Cycle detection for simulations whose states eventually repeat.
+//!
+//! A sequence `x0, x1, x2, ...` that cycles is described by an `offset` (the index of the first
+//! state that is part of the cycle) and a `period` (the length of the cycle).
+use std::collections::HashMap;
+use std::hash::Hash;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    pub offset: usize,
+    pub period: usize,
+}
+
+impl Cycle {
+    /// The earliest step that has the same state as step `n`.
+    pub fn equivalent_step(&self, n: usize) -> usize {
+        if n < self.offset {
+            n
+        } else {
+            self.offset + (n - self.offset) % self.period
+        }
+    }
+}
+
+/// Detects a cycle in a sequence of hashable states by remembering every state seen so far.
+/// Returns `None` if the sequence ends without repeating a state.
+pub fn find_cycle<S: Eq + Hash>(states: impl IntoIterator<Item = S>) -> Option<Cycle> {
+    let mut seen = HashMap::new();
+    for (step, state) in states.into_iter().enumerate() {
+        if let Some(offset) = seen.insert(state, step) {
+            return Some(Cycle {
+                offset,
+                period: step - offset,
+            });
+        }
+    }
+    None
+}
+
+/// The state after `n` steps. Stops simulating at the first repeated state and extrapolates from
+/// the cycle, so `n` can be far larger than the number of distinct states.
+/// Returns `None` if the sequence ends before step `n`.
+pub fn state_at<S: Eq + Hash + Clone>(states: impl IntoIterator<Item = S>, n: usize) -> Option<S> {
+    let mut seen = HashMap::new();
+    let mut history = vec![];
+    for (step, state) in states.into_iter().enumerate() {
+        if step == n {
+            return Some(state);
+        }
+        if let Some(offset) = seen.insert(state.clone(), step) {
+            let cycle = Cycle {
+                offset,
+                period: step - offset,
+            };
+            return Some(history.swap_remove(cycle.equivalent_step(n)));
+        }
+        history.push(state);
+    }
+    None
+}
+
+/// Floyd's tortoise and hare. Uses constant memory but `step` must eventually cycle.
+pub fn floyd<S: PartialEq + Clone>(start: S, mut step: impl FnMut(&S) -> S) -> Cycle {
+    let mut tortoise = step(&start);
+    let mut hare = step(&tortoise);
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        hare = step(&hare);
+    }
+
+    let mut offset = 0;
+    tortoise = start;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        offset += 1;
+    }
+
+    let mut period = 1;
+    hare = step(&tortoise);
+    while tortoise != hare {
+        hare = step(&hare);
+        period += 1;
+    }
+
+    Cycle { offset, period }
+}
+
+/// Brent's algorithm. Like [`floyd`], but needs fewer calls to `step`.
+pub fn brent<S: PartialEq + Clone>(start: S, mut step: impl FnMut(&S) -> S) -> Cycle {
+    let mut power = 1;
+    let mut period = 1;
+    let mut tortoise = start.clone();
+    let mut hare = step(&start);
+    while tortoise != hare {
+        if power == period {
+            tortoise = hare.clone();
+            power *= 2;
+            period = 0;
+        }
+        hare = step(&hare);
+        period += 1;
+    }
+
+    tortoise = start.clone();
+    hare = start;
+    for _ in 0..period {
+        hare = step(&hare);
+    }
+
+    let mut offset = 0;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        offset += 1;
+    }
+
+    Cycle { offset, period }
+}
+
+#[cfg(feature = "test_lib")]
+mod tests {
+    use super::{brent, find_cycle, floyd, state_at, Cycle};
+
+    // 0 -> 1 -> 2 -> 3 -> 4 -> 5 -> 3 -> ...
+    fn step(x: &u32) -> u32 {
+        if *x == 5 {
+            3
+        } else {
+            x + 1
+        }
+    }
+
+    fn states() -> impl Iterator<Item = u32> {
+        std::iter::successors(Some(0), |x| Some(step(x)))
+    }
+
+    const EXPECTED: Cycle = Cycle {
+        offset: 3,
+        period: 3,
+    };
+
+    #[test]
+    fn finds_cycle_by_hashing() {
+        assert_eq!(find_cycle(states()), Some(EXPECTED));
+    }
+
+    #[test]
+    fn finds_no_cycle_in_finite_sequence() {
+        assert_eq!(find_cycle(0..10), None);
+    }
+
+    #[test]
+    fn floyd_finds_cycle() {
+        assert_eq!(floyd(0, step), EXPECTED);
+    }
+
+    #[test]
+    fn brent_finds_cycle() {
+        assert_eq!(brent(0, step), EXPECTED);
+    }
+
+    #[test]
+    fn detects_pure_cycle() {
+        let expected = Cycle {
+            offset: 0,
+            period: 7,
+        };
+        assert_eq!(brent(0, |x| (x + 1) % 7), expected);
+        assert_eq!(floyd(0, |x| (x + 1) % 7), expected);
+    }
+
+    #[test]
+    fn extrapolates_state() {
+        assert_eq!(state_at(states(), 2), Some(2));
+        assert_eq!(state_at(states(), 1_000_000_000), Some(4));
+        assert_eq!(state_at(0..10, 20), None);
+    }
+
+    #[test]
+    fn maps_steps_into_first_cycle() {
+        assert_eq!(EXPECTED.equivalent_step(1), 1);
+        assert_eq!(EXPECTED.equivalent_step(6), 3);
+        assert_eq!(EXPECTED.equivalent_step(10), 4);
+    }
+}
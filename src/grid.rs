@@ -0,0 +1,284 @@
+//! Dense 2D grids and their rendering as text, for visual debugging of simulations.
+//!
+//! Positions are `(x, y)` with the origin in the top left corner. A [`Render`] draws every cell
+//! with a formatter, then draws overlays on top of it, e.g. a visited path or a set of points.
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+use crate::template::ANSI_RESET;
+
+pub type Position = (usize, usize);
+
+/// A rectangular grid of cells stored row by row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn from_fn(width: usize, height: usize, mut cell: impl FnMut(Position) -> T) -> Self {
+        let cells = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(&mut cell)
+            .collect();
+        Self {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    /// Parses one cell per character. Every line must have the same length.
+    pub fn parse(input: &str, mut cell: impl FnMut(char) -> T) -> Self {
+        let mut width = 0;
+        let mut height = 0;
+        let mut cells = vec![];
+        for line in input.lines() {
+            let before = cells.len();
+            cells.extend(line.chars().map(&mut cell));
+            width = cells.len() - before;
+            height += 1;
+        }
+        assert_eq!(width * height, cells.len(), "Grid should be rectangular");
+        Self {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, (x, y): Position) -> bool {
+        x < self.width && y < self.height
+    }
+
+    pub fn get(&self, position: Position) -> Option<&T> {
+        self.contains(position)
+            .then(|| &self.cells[position.1 * self.width + position.0])
+    }
+
+    pub fn get_mut(&mut self, position: Position) -> Option<&mut T> {
+        self.contains(position)
+            .then(|| &mut self.cells[position.1 * self.width + position.0])
+    }
+
+    /// Every position with its cell, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = (Position, &T)> {
+        let width = self.width;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(i, cell)| ((i % width, i / width), cell))
+    }
+
+    /// Starts a render of the grid, drawing each cell with `cell`.
+    pub fn render<'a, C: Into<Cell>>(&'a self, cell: impl Fn(&T) -> C + 'a) -> Render<'a> {
+        Render::new(self.width, self.height, move |position| {
+            cell(&self[position])
+        })
+    }
+}
+
+impl<T> Index<Position> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, position: Position) -> &T {
+        self.get(position)
+            .expect("Position should be inside the grid")
+    }
+}
+
+impl<T> IndexMut<Position> for Grid<T> {
+    fn index_mut(&mut self, position: Position) -> &mut T {
+        self.get_mut(position)
+            .expect("Position should be inside the grid")
+    }
+}
+
+/// A glyph with an optional ANSI style such as `ANSI_BOLD` or `ANSI_RED`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    pub glyph: char,
+    pub style: Option<&'static str>,
+}
+
+impl Cell {
+    pub fn styled(glyph: char, style: &'static str) -> Self {
+        Self {
+            glyph,
+            style: Some(style),
+        }
+    }
+}
+
+impl From<char> for Cell {
+    fn from(glyph: char) -> Self {
+        Self { glyph, style: None }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Overlay {
+    Cell(Cell),
+    Style(&'static str),
+}
+
+/// Renders a grid to text via its `Display` implementation, one line per row.
+pub struct Render<'a> {
+    width: usize,
+    height: usize,
+    cell: Box<dyn Fn(Position) -> Cell + 'a>,
+    overlays: HashMap<Position, Overlay>,
+    colour: bool,
+}
+
+impl<'a> Render<'a> {
+    /// Renders a `width` x `height` area, e.g. of a `HashMap` based grid, drawing each position
+    /// with `cell`.
+    pub fn new<C: Into<Cell>>(
+        width: usize,
+        height: usize,
+        cell: impl Fn(Position) -> C + 'a,
+    ) -> Self {
+        Self {
+            width,
+            height,
+            cell: Box::new(move |position| cell(position).into()),
+            overlays: HashMap::new(),
+            colour: false,
+        }
+    }
+
+    /// Draws `cell` over every position, e.g. a visited path or a set of points.
+    /// Later overlays are drawn on top of earlier ones. Positions outside the area are ignored.
+    pub fn overlay(
+        mut self,
+        positions: impl IntoIterator<Item = Position>,
+        cell: impl Into<Cell>,
+    ) -> Self {
+        let cell = cell.into();
+        self.overlays
+            .extend(positions.into_iter().map(|p| (p, Overlay::Cell(cell))));
+        self
+    }
+
+    /// Keeps the glyphs at every position but draws them with `style`.
+    pub fn highlight(
+        mut self,
+        positions: impl IntoIterator<Item = Position>,
+        style: &'static str,
+    ) -> Self {
+        for position in positions {
+            let overlay = match self.overlays.get(&position) {
+                Some(Overlay::Cell(cell)) => Overlay::Cell(Cell::styled(cell.glyph, style)),
+                _ => Overlay::Style(style),
+            };
+            self.overlays.insert(position, overlay);
+        }
+        self
+    }
+
+    /// Emits ANSI styles. Without this, styles are dropped and the output is plain text.
+    pub fn coloured(mut self) -> Self {
+        self.colour = true;
+        self
+    }
+
+    fn cell(&self, position: Position) -> Cell {
+        match self.overlays.get(&position) {
+            Some(Overlay::Cell(cell)) => *cell,
+            Some(Overlay::Style(style)) => Cell::styled((self.cell)(position).glyph, style),
+            None => (self.cell)(position),
+        }
+    }
+}
+
+impl fmt::Display for Render<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for y in 0..self.height {
+            if y > 0 {
+                writeln!(f)?;
+            }
+            for x in 0..self.width {
+                match self.cell((x, y)) {
+                    Cell {
+                        glyph,
+                        style: Some(style),
+                    } if self.colour => write!(f, "{style}{glyph}{ANSI_RESET}")?,
+                    Cell { glyph, .. } => write!(f, "{glyph}")?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "test_lib")]
+mod tests {
+    use super::{Cell, Grid, Render};
+    use crate::template::{ANSI_BOLD, ANSI_RED, ANSI_RESET};
+
+    const MAP: &str = "....#\n.#...\n...#.";
+
+    #[test]
+    fn parses_and_renders_round_trip() {
+        let grid = Grid::parse(MAP, |c| c == '#');
+        assert_eq!((grid.width(), grid.height()), (5, 3));
+        assert!(grid[(4, 0)]);
+        assert_eq!(grid.get((5, 0)), None);
+        let rendered = grid
+            .render(|&wall| if wall { '#' } else { '.' })
+            .to_string();
+        assert_eq!(rendered, MAP);
+    }
+
+    #[test]
+    fn draws_overlays_in_order() {
+        let grid = Grid::parse(MAP, |c| c);
+        let rendered = grid
+            .render(|&c| c)
+            .overlay([(0, 0), (1, 0), (2, 0)], 'X')
+            .overlay([(2, 0), (9, 9)], 'O')
+            .to_string();
+        assert_eq!(rendered, "XXO.#\n.#...\n...#.");
+    }
+
+    #[test]
+    fn renders_sparse_grid() {
+        let rendered = Render::new(3, 2, |(x, y)| if x == y { '\\' } else { ' ' }).to_string();
+        assert_eq!(rendered, "\\  \n \\ ");
+    }
+
+    #[test]
+    fn emits_styles_only_when_coloured() {
+        let grid = Grid::from_fn(2, 1, |(x, _)| if x == 0 { '@' } else { '.' });
+        let render = || {
+            grid.render(|&c| match c {
+                '@' => Cell::styled(c, ANSI_BOLD),
+                _ => c.into(),
+            })
+        };
+        assert_eq!(render().to_string(), "@.");
+        assert_eq!(
+            render().coloured().to_string(),
+            format!("{ANSI_BOLD}@{ANSI_RESET}.")
+        );
+        assert_eq!(
+            render()
+                .highlight([(1, 0)], ANSI_RED)
+                .coloured()
+                .to_string(),
+            format!("{ANSI_BOLD}@{ANSI_RESET}{ANSI_RED}.{ANSI_RESET}")
+        );
+    }
+}
@@ -0,0 +1,683 @@
+//! Dependency-free image output: PPM and PNG stills, animated GIFs and numbered frame sequences.
+//!
+//! Grids are drawn as blocks of `cell_size` x `cell_size` pixels. Text renders (as produced by
+//! [`crate::grid::Render`] or a [`crate::simulation::Simulation`]) are mapped to colours with a
+//! [`Palette`], one cell per character.
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::grid::Position;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    pub const BLACK: Rgb = Rgb(0, 0, 0);
+    pub const WHITE: Rgb = Rgb(255, 255, 255);
+
+    /// A colour that is easy to tell apart from the colours of nearby indices, e.g. for regions.
+    pub fn distinct(index: usize) -> Self {
+        // step around the colour wheel by the golden angle
+        let hue = (index as f64 * 137.507_764) % 360.0;
+        let sector = hue / 60.0;
+        let x = 1.0 - (sector % 2.0 - 1.0).abs();
+        let (r, g, b) = match sector as u32 {
+            0 => (1.0, x, 0.0),
+            1 => (x, 1.0, 0.0),
+            2 => (0.0, 1.0, x),
+            3 => (0.0, x, 1.0),
+            4 => (x, 0.0, 1.0),
+            _ => (1.0, 0.0, x),
+        };
+        let channel = |c: f64| (55.0 + c * 200.0) as u8;
+        Rgb(channel(r), channel(g), channel(b))
+    }
+}
+
+impl FromStr for Rgb {
+    type Err = String;
+
+    /// Parses `rrggbb` hex, with or without a leading `#`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.strip_prefix('#').unwrap_or(s);
+        let channel = |i: usize| {
+            hex.get(i..i + 2)
+                .and_then(|c| u8::from_str_radix(c, 16).ok())
+                .ok_or_else(|| format!("Invalid colour: {s}"))
+        };
+        if hex.len() != 6 {
+            return Err(format!("Invalid colour: {s}"));
+        }
+        Ok(Rgb(channel(0)?, channel(2)?, channel(4)?))
+    }
+}
+
+/// Maps the characters of a text render to colours.
+#[derive(Debug, Clone)]
+pub struct Palette {
+    colours: HashMap<char, Rgb>,
+}
+
+impl Default for Palette {
+    /// Spaces and `.` are black, every other character gets its own distinct colour.
+    fn default() -> Self {
+        Self {
+            colours: HashMap::from([(' ', Rgb::BLACK), ('.', Rgb::BLACK)]),
+        }
+    }
+}
+
+impl Palette {
+    pub fn with(mut self, glyph: char, colour: Rgb) -> Self {
+        self.colours.insert(glyph, colour);
+        self
+    }
+
+    pub fn colour(&self, glyph: char) -> Rgb {
+        self.colours
+            .get(&glyph)
+            .copied()
+            .unwrap_or_else(|| Rgb::distinct(glyph as usize))
+    }
+}
+
+impl Palette {
+    /// Applies overrides such as `#=808080,O=ffcc00`.
+    pub fn with_overrides(self, overrides: &str) -> Result<Self, String> {
+        overrides
+            .split(',')
+            .filter(|entry| !entry.is_empty())
+            .try_fold(self, |palette, entry| {
+                let mut chars = entry.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(glyph), Some('=')) => Ok(palette.with(glyph, chars.as_str().parse()?)),
+                    _ => Err(format!("Invalid palette entry: {entry}")),
+                }
+            })
+    }
+}
+
+impl FromStr for Palette {
+    type Err = String;
+
+    /// Parses overrides of the default palette.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Palette::default().with_overrides(s)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<Rgb>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize, background: Rgb) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![background; width * height],
+        }
+    }
+
+    /// Draws a `columns` x `rows` grid, filling each cell with the colour of its position.
+    pub fn from_cells(
+        columns: usize,
+        rows: usize,
+        cell_size: usize,
+        colour: impl Fn(Position) -> Rgb,
+    ) -> Self {
+        let mut image = Self::new(columns * cell_size, rows * cell_size, Rgb::BLACK);
+        for y in 0..rows {
+            for x in 0..columns {
+                image.fill_cell((x, y), cell_size, colour((x, y)));
+            }
+        }
+        image
+    }
+
+    /// Draws a text render, one cell per character. ANSI styles are ignored and short lines are
+    /// padded with the colour of a space.
+    pub fn from_text(text: &str, cell_size: usize, palette: &Palette) -> Self {
+        let rows: Vec<Vec<char>> = text.lines().map(strip_ansi).collect();
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        Self::from_cells(columns, rows.len(), cell_size, |(x, y)| {
+            palette.colour(rows[y].get(x).copied().unwrap_or(' '))
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, (x, y): Position) -> Option<Rgb> {
+        (x < self.width && y < self.height).then(|| self.pixels[y * self.width + x])
+    }
+
+    pub fn set(&mut self, (x, y): Position, colour: Rgb) {
+        if x < self.width && y < self.height {
+            self.pixels[y * self.width + x] = colour;
+        }
+    }
+
+    /// Fills the `cell_size` x `cell_size` block of the cell at `position`.
+    pub fn fill_cell(&mut self, (x, y): Position, cell_size: usize, colour: Rgb) {
+        for dy in 0..cell_size {
+            for dx in 0..cell_size {
+                self.set((x * cell_size + dx, y * cell_size + dy), colour);
+            }
+        }
+    }
+
+    fn rgb_bytes(&self) -> impl Iterator<Item = u8> + '_ {
+        self.pixels.iter().flat_map(|&Rgb(r, g, b)| [r, g, b])
+    }
+
+    /// Writes a binary PPM (P6) image.
+    pub fn write_ppm(&self, out: &mut impl Write) -> io::Result<()> {
+        write!(out, "P6\n{} {}\n255\n", self.width, self.height)?;
+        out.write_all(&self.rgb_bytes().collect::<Vec<_>>())
+    }
+
+    /// Writes a truecolour PNG. The image data is stored without compression.
+    pub fn write_png(&self, out: &mut impl Write) -> io::Result<()> {
+        out.write_all(b"\x89PNG\r\n\x1a\n")?;
+
+        let mut header = vec![];
+        header.extend((self.width as u32).to_be_bytes());
+        header.extend((self.height as u32).to_be_bytes());
+        // 8 bit depth, truecolour, default compression, filter and interlace methods
+        header.extend([8, 2, 0, 0, 0]);
+        write_png_chunk(out, b"IHDR", &header)?;
+
+        let mut scanlines = Vec::with_capacity(self.height * (self.width * 3 + 1));
+        for row in self.pixels.chunks(self.width.max(1)).take(self.height) {
+            // filter type 0 (none)
+            scanlines.push(0);
+            scanlines.extend(row.iter().flat_map(|&Rgb(r, g, b)| [r, g, b]));
+        }
+        write_png_chunk(out, b"IDAT", &zlib_stored(&scanlines))?;
+        write_png_chunk(out, b"IEND", &[])
+    }
+
+    /// Writes the image in `format`. A GIF holds just this one frame.
+    pub fn write(&self, format: Format, out: &mut impl Write) -> io::Result<()> {
+        match format {
+            Format::Ppm => self.write_ppm(out),
+            Format::Png => self.write_png(out),
+            Format::Gif => {
+                let mut gif = GifWriter::new(out, self.width, self.height, 0)?;
+                gif.write_frame(self)?;
+                gif.finish()
+            }
+        }
+    }
+
+    /// Saves the image to `path` in `format`.
+    pub fn save(&self, path: impl AsRef<Path>, format: Format) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        self.write(format, &mut out)?;
+        out.flush()
+    }
+}
+
+/// Drops ANSI escape sequences such as `\x1b[1m` from a line.
+fn strip_ansi(line: &str) -> Vec<char> {
+    let mut chars = vec![];
+    let mut in_escape = false;
+    for c in line.chars() {
+        match (in_escape, c) {
+            (false, '\x1b') => in_escape = true,
+            (false, _) => chars.push(c),
+            (true, c) if c.is_ascii_alphabetic() => in_escape = false,
+            (true, _) => {}
+        }
+    }
+    chars
+}
+
+fn write_png_chunk(out: &mut impl Write, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+    let crc = crc32(kind.iter().chain(data));
+    out.write_all(&crc.to_be_bytes())
+}
+
+fn crc32<'a>(bytes: impl IntoIterator<Item = &'a u8>) -> u32 {
+    let mut table = [0_u32; 256];
+    for (n, entry) in table.iter_mut().enumerate() {
+        *entry = (0..8).fold(n as u32, |c, _| {
+            if c & 1 == 1 {
+                0xEDB8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            }
+        });
+    }
+    !bytes.into_iter().fold(!0_u32, |crc, &byte| {
+        table[((crc ^ u32::from(byte)) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+/// Wraps `data` in a zlib stream of uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let mut blocks = data.chunks(u16::MAX as usize).peekable();
+    if blocks.peek().is_none() {
+        // a final, empty block
+        out.extend([1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        out.push(u8::from(blocks.peek().is_none()));
+        let len = block.len() as u16;
+        out.extend(len.to_le_bytes());
+        out.extend((!len).to_le_bytes());
+        out.extend(block);
+    }
+
+    let (a, b) = data.iter().fold((1_u32, 0_u32), |(a, b), &byte| {
+        let a = (a + u32::from(byte)) % 65521;
+        (a, (b + a) % 65521)
+    });
+    out.extend(((b << 16) | a).to_be_bytes());
+    out
+}
+
+/// Streams an animated GIF frame by frame. Every frame gets its own colour table, so each may
+/// use at most 256 colours.
+pub struct GifWriter<W: Write> {
+    out: W,
+    width: u16,
+    height: u16,
+    delay: u16,
+}
+
+impl<W: Write> GifWriter<W> {
+    /// Starts an endlessly looping GIF showing each frame for `delay` hundredths of a second,
+    /// until [`GifWriter::set_delay`] changes it.
+    pub fn new(mut out: W, width: usize, height: usize, delay: u16) -> io::Result<Self> {
+        let width = u16::try_from(width).map_err(|_| invalid("GIF is too wide"))?;
+        let height = u16::try_from(height).map_err(|_| invalid("GIF is too high"))?;
+        out.write_all(b"GIF89a")?;
+        out.write_all(&width.to_le_bytes())?;
+        out.write_all(&height.to_le_bytes())?;
+        // no global colour table, background colour 0, square pixels
+        out.write_all(&[0, 0, 0])?;
+        // NETSCAPE2.0 extension: loop forever
+        out.write_all(b"\x21\xFF\x0BNETSCAPE2.0\x03\x01\x00\x00\x00")?;
+
+        Ok(Self {
+            out,
+            width,
+            height,
+            delay,
+        })
+    }
+
+    pub fn write_frame(&mut self, image: &Image) -> io::Result<()> {
+        if (image.width, image.height) != (self.width.into(), self.height.into()) {
+            return Err(invalid("GIF frames must all have the same size"));
+        }
+
+        let mut colours: Vec<Rgb> = vec![];
+        let mut lookup = HashMap::new();
+        let indices: Vec<u8> = image
+            .pixels
+            .iter()
+            .map(|&colour| {
+                *lookup.entry(colour).or_insert_with(|| {
+                    colours.push(colour);
+                    colours.len() - 1
+                }) as u8
+            })
+            .collect();
+        if colours.len() > 256 {
+            return Err(invalid("GIF frames may use at most 256 colours"));
+        }
+
+        // the colour table needs a power of two entries, at least 4 for LZW's minimum code size
+        let table_bits = (colours.len().max(4) as u32)
+            .next_power_of_two()
+            .trailing_zeros();
+        colours.resize(1 << table_bits, Rgb::BLACK);
+
+        // graphic control extension, applies to the next frame only
+        self.out.write_all(&[0x21, 0xF9, 0x04, 0x00])?;
+        self.out.write_all(&self.delay.to_le_bytes())?;
+        self.out.write_all(&[0x00, 0x00])?;
+
+        // image descriptor with a local colour table
+        self.out.write_all(&[0x2C, 0, 0, 0, 0])?;
+        self.out.write_all(&self.width.to_le_bytes())?;
+        self.out.write_all(&self.height.to_le_bytes())?;
+        self.out.write_all(&[0x80 | (table_bits as u8 - 1)])?;
+        self.out.write_all(
+            &colours
+                .iter()
+                .flat_map(|&Rgb(r, g, b)| [r, g, b])
+                .collect::<Vec<_>>(),
+        )?;
+
+        let min_code_size = table_bits as u8;
+        self.out.write_all(&[min_code_size])?;
+        for block in lzw_encode(&indices, min_code_size).chunks(255) {
+            self.out.write_all(&[block.len() as u8])?;
+            self.out.write_all(block)?;
+        }
+        self.out.write_all(&[0])
+    }
+
+    /// Sets the display time, in hundredths of a second, of all following frames.
+    pub fn set_delay(&mut self, delay: u16) {
+        self.delay = delay;
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        self.out.write_all(&[0x3B])?;
+        self.out.flush()
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+/// GIF flavoured LZW: variable code sizes up to 12 bits, packed least significant bit first.
+fn lzw_encode(indices: &[u8], min_code_size: u8) -> Vec<u8> {
+    const MAX_CODE: u16 = 4095;
+    let clear = 1_u16 << min_code_size;
+    let end = clear + 1;
+
+    let mut out = vec![];
+    let mut buffer = 0_u32;
+    let mut bits = 0;
+    let mut emit = |code: u16, size: u8, out: &mut Vec<u8>| {
+        buffer |= u32::from(code) << bits;
+        bits += size;
+        while bits >= 8 {
+            out.push(buffer as u8);
+            buffer >>= 8;
+            bits -= 8;
+        }
+    };
+
+    let mut code_size = min_code_size + 1;
+    let mut next_code = end + 1;
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    emit(clear, code_size, &mut out);
+
+    let mut pixels = indices.iter();
+    if let Some(&first) = pixels.next() {
+        let mut prefix = u16::from(first);
+        for &pixel in pixels {
+            if let Some(&code) = table.get(&(prefix, pixel)) {
+                prefix = code;
+                continue;
+            }
+            emit(prefix, code_size, &mut out);
+            table.insert((prefix, pixel), next_code);
+            // the decoder reads codes one entry behind, so widen once the table outgrows the code
+            if next_code == 1 << code_size {
+                code_size += 1;
+            }
+            next_code += 1;
+            if next_code > MAX_CODE {
+                emit(clear, code_size, &mut out);
+                table.clear();
+                code_size = min_code_size + 1;
+                next_code = end + 1;
+            }
+            prefix = u16::from(pixel);
+        }
+        emit(prefix, code_size, &mut out);
+    }
+    emit(end, code_size, &mut out);
+    if bits > 0 {
+        out.push(buffer as u8);
+    }
+    out
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Png,
+    Ppm,
+    Gif,
+}
+
+impl Format {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Png => "png",
+            Format::Ppm => "ppm",
+            Format::Gif => "gif",
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "png" => Ok(Format::Png),
+            "ppm" => Ok(Format::Ppm),
+            "gif" => Ok(Format::Gif),
+            _ => Err(format!("Unknown image format: {s}")),
+        }
+    }
+}
+
+/// Writes frames into a directory, either as numbered stills (`frame_00000.png`, ...) or, for
+/// [`Format::Gif`], as a single `animation.gif`.
+pub struct FrameWriter {
+    dir: PathBuf,
+    format: Format,
+    delay: u16,
+    frames: usize,
+    gif: Option<GifWriter<BufWriter<File>>>,
+}
+
+impl FrameWriter {
+    /// Creates `dir` if needed. `delay` is the time per frame of a GIF in hundredths of a second.
+    pub fn new(dir: impl Into<PathBuf>, format: Format, delay: u16) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            format,
+            delay,
+            frames: 0,
+            gif: None,
+        })
+    }
+
+    pub fn write(&mut self, image: &Image) -> io::Result<()> {
+        match self.format {
+            Format::Gif => {
+                if self.gif.is_none() {
+                    let file = BufWriter::new(File::create(self.dir.join("animation.gif"))?);
+                    let gif = GifWriter::new(file, image.width, image.height, self.delay)?;
+                    self.gif = Some(gif);
+                }
+                let gif = self.gif.as_mut().expect("GIF was just created");
+                gif.write_frame(image)?;
+            }
+            format => {
+                let name = format!("frame_{:05}.{}", self.frames, format.extension());
+                image.save(self.dir.join(name), format)?;
+            }
+        }
+        self.frames += 1;
+        Ok(())
+    }
+
+    /// Finishes the animation, if any, and returns the number of frames written.
+    pub fn finish(self) -> io::Result<usize> {
+        if let Some(gif) = self.gif {
+            gif.finish()?;
+        }
+        Ok(self.frames)
+    }
+}
+
+#[cfg(feature = "test_lib")]
+mod tests {
+    use super::{crc32, lzw_encode, zlib_stored, GifWriter, Image, Palette, Rgb};
+
+    /// Minimal GIF LZW decoder, written from the spec independently of the encoder.
+    fn lzw_decode(data: &[u8], min_code_size: u8) -> Vec<u8> {
+        let clear = 1_usize << min_code_size;
+        let end = clear + 1;
+        let reset = || -> Vec<Vec<u8>> { (0..clear + 2).map(|i| vec![i as u8]).collect() };
+        let mut table = reset();
+        let mut code_size = min_code_size as usize + 1;
+        let mut out = vec![];
+        let mut previous: Option<usize> = None;
+        let mut bit = 0;
+        loop {
+            let code = (0..code_size).fold(0, |code, i| {
+                let b = bit + i;
+                code | (((data[b / 8] >> (b % 8)) & 1) as usize) << i
+            });
+            bit += code_size;
+            if code == clear {
+                table = reset();
+                code_size = min_code_size as usize + 1;
+                previous = None;
+                continue;
+            }
+            if code == end {
+                return out;
+            }
+            let entry = match (table.get(code), previous) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(p)) => {
+                    let mut entry = table[p].clone();
+                    entry.push(table[p][0]);
+                    entry
+                }
+                (None, None) => panic!("invalid first code"),
+            };
+            if let Some(p) = previous {
+                let mut new = table[p].clone();
+                new.push(entry[0]);
+                if table.len() < 4096 {
+                    table.push(new);
+                }
+            }
+            if table.len() == 1 << code_size && code_size < 12 {
+                code_size += 1;
+            }
+            out.extend(&entry);
+            previous = Some(code);
+        }
+    }
+
+    #[test]
+    fn lzw_round_trips() {
+        let mut seed = 1_u32;
+        let mut random = || {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (seed >> 16) as u8
+        };
+        let inputs: Vec<(Vec<u8>, u8)> = vec![
+            (vec![], 2),
+            (vec![1], 2),
+            (vec![0, 0, 0, 0, 1, 1, 2, 3, 0, 0, 0, 0], 2),
+            ((0..20_000).map(|_| random() % 4).collect(), 2),
+            ((0..50_000).map(|_| random()).collect(), 8),
+            (vec![7; 100_000], 3),
+        ];
+        for (input, min_code_size) in inputs {
+            let encoded = lzw_encode(&input, min_code_size);
+            assert_eq!(lzw_decode(&encoded, min_code_size), input);
+        }
+    }
+
+    #[test]
+    fn delays_every_gif_frame() {
+        let frames = [Rgb::BLACK, Rgb(255, 0, 0), Rgb(0, 0, 255)].map(|colour| {
+            let mut image = Image::new(2, 2, colour);
+            image.set((1, 1), Rgb::BLACK);
+            image
+        });
+        let mut out = vec![];
+        let mut gif = GifWriter::new(&mut out, 2, 2, 5).unwrap();
+        gif.write_frame(&frames[0]).unwrap();
+        gif.write_frame(&frames[1]).unwrap();
+        gif.set_delay(20);
+        gif.write_frame(&frames[2]).unwrap();
+        gif.finish().unwrap();
+
+        // each graphic control extension is followed by its block size, flags and the delay
+        let delays: Vec<u16> = out
+            .windows(6)
+            .filter(|window| window[..2] == [0x21, 0xF9])
+            .map(|window| u16::from_le_bytes([window[4], window[5]]))
+            .collect();
+        assert_eq!(delays, [5, 5, 20]);
+    }
+
+    #[test]
+    fn computes_checksums() {
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        // zlib header, one stored final block and the adler32 checksum of "abc"
+        let stream = zlib_stored(b"abc");
+        assert_eq!(
+            stream,
+            [0x78, 0x01, 1, 3, 0, 0xFC, 0xFF, b'a', b'b', b'c', 0x02, 0x4D, 0x01, 0x27]
+        );
+    }
+
+    #[test]
+    fn writes_ppm() {
+        let image = Image::from_cells(
+            2,
+            1,
+            1,
+            |(x, _)| if x == 0 { Rgb::WHITE } else { Rgb(1, 2, 3) },
+        );
+        let mut out = vec![];
+        image.write_ppm(&mut out).unwrap();
+        assert_eq!(out, b"P6\n2 1\n255\n\xFF\xFF\xFF\x01\x02\x03");
+    }
+
+    #[test]
+    fn writes_png() {
+        let image = Image::new(3, 2, Rgb(10, 20, 30));
+        let mut out = vec![];
+        image.write_png(&mut out).unwrap();
+        assert!(out.starts_with(b"\x89PNG\r\n\x1a\n\0\0\0\x0DIHDR\0\0\0\x03\0\0\0\x02"));
+        assert!(out.ends_with(b"\0\0\0\0IEND\xAE\x42\x60\x82"));
+    }
+
+    #[test]
+    fn draws_text_with_palette() {
+        let palette: Palette = "#=ff0000,O=00ff00".parse().unwrap();
+        let image = Image::from_text("#O\n\x1b[1m.\x1b[0m", 2, &palette);
+        assert_eq!((image.width(), image.height()), (4, 4));
+        assert_eq!(image.get((1, 1)), Some(Rgb(255, 0, 0)));
+        assert_eq!(image.get((2, 0)), Some(Rgb(0, 255, 0)));
+        assert_eq!(image.get((0, 3)), Some(Rgb::BLACK));
+        assert_eq!(image.get((3, 3)), Some(Rgb::BLACK));
+        assert!("#=red".parse::<Palette>().is_err());
+    }
+
+    #[test]
+    fn distinct_colours_differ() {
+        let colours: std::collections::HashSet<_> = (0..50).map(Rgb::distinct).collect();
+        assert_eq!(colours.len(), 50);
+    }
+}
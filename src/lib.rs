@@ -1,3 +1,11 @@
+pub mod cycle;
+pub mod grid;
+pub mod image;
+pub mod linear;
+pub mod math;
+pub mod multiset;
+pub mod search;
+pub mod simulation;
 pub mod template;
 
 // Use this file to add helper functions and additional modules.
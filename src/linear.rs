@@ -0,0 +1,178 @@
+//! Exact solver for square systems of linear equations.
+//!
+//! Elimination is fraction-free (Bareiss), so every intermediate value is an integer minor of the
+//! input and no precision is lost. Only the final back substitution works with rationals.
+use num_rational::Ratio;
+
+pub type Rational = Ratio<i128>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Solution {
+    /// Exactly one solution, one value per unknown.
+    Unique(Vec<Rational>),
+    /// The equations contradict each other.
+    None,
+    /// The equations are consistent but do not determine every unknown.
+    Infinite,
+}
+
+impl Solution {
+    /// The unique solution if all of its values are integers.
+    pub fn integers(&self) -> Option<Vec<i128>> {
+        match self {
+            Solution::Unique(values) => values
+                .iter()
+                .map(|value| value.is_integer().then(|| value.to_integer()))
+                .collect(),
+            _ => None,
+        }
+    }
+}
+
+/// Solves `matrix * x = rhs` for a square integer `matrix`. Panics on overflow in debug builds.
+pub fn solve(matrix: &[Vec<i128>], rhs: &[i128]) -> Solution {
+    let n = matrix.len();
+    assert_eq!(rhs.len(), n, "rhs should have one value per equation");
+    assert!(
+        matrix.iter().all(|row| row.len() == n),
+        "matrix should be square"
+    );
+
+    // augmented matrix [matrix | rhs]
+    let mut rows: Vec<Vec<i128>> = matrix
+        .iter()
+        .zip(rhs)
+        .map(|(row, &value)| row.iter().copied().chain([value]).collect())
+        .collect();
+
+    let mut rank = 0;
+    let mut previous_pivot = 1;
+    for col in 0..n {
+        let Some(pivot_row) = (rank..n).find(|&row| rows[row][col] != 0) else {
+            continue;
+        };
+        rows.swap(rank, pivot_row);
+        let (upper, lower) = rows.split_at_mut(rank + 1);
+        let pivot_values = &upper[rank];
+        let pivot = pivot_values[col];
+        for row in lower {
+            let factor = row[col];
+            for (value, pivot_value) in row.iter_mut().zip(pivot_values).skip(col + 1) {
+                *value = (pivot * *value - factor * pivot_value) / previous_pivot;
+            }
+            row[col] = 0;
+        }
+        previous_pivot = pivot;
+        rank += 1;
+    }
+
+    // below the rank every coefficient is 0, so a non-zero rhs is a contradiction.
+    if rows[rank..].iter().any(|row| row[n] != 0) {
+        return Solution::None;
+    }
+    if rank < n {
+        return Solution::Infinite;
+    }
+
+    let mut solution = vec![Rational::from_integer(0); n];
+    for i in (0..n).rev() {
+        let known: Rational = (i + 1..n)
+            .map(|j| solution[j] * rows[i][j])
+            .fold(Rational::from_integer(0), |acc, term| acc + term);
+        solution[i] = (Rational::from_integer(rows[i][n]) - known) / rows[i][i];
+    }
+    Solution::Unique(solution)
+}
+
+/// Solves `matrix * x = rhs` for a square rational `matrix`.
+pub fn solve_rational(matrix: &[Vec<Rational>], rhs: &[Rational]) -> Solution {
+    // scaling an equation by the lcm of its denominators does not change its solutions.
+    let (matrix, rhs): (Vec<_>, Vec<_>) = matrix
+        .iter()
+        .zip(rhs)
+        .map(|(row, value)| {
+            let scale = row
+                .iter()
+                .chain([value])
+                .fold(1, |acc, x| crate::math::lcm(acc, *x.denom()));
+            let scaled = |x: &Rational| (x * scale).to_integer();
+            (row.iter().map(scaled).collect::<Vec<_>>(), scaled(value))
+        })
+        .unzip();
+    solve(&matrix, &rhs)
+}
+
+#[cfg(feature = "test_lib")]
+mod tests {
+    use super::{solve, solve_rational, Rational, Solution};
+
+    fn unique(values: &[(i128, i128)]) -> Solution {
+        Solution::Unique(
+            values
+                .iter()
+                .map(|&(numer, denom)| Rational::new(numer, denom))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn solves_unique_system() {
+        let matrix = vec![vec![94, 22], vec![34, 67]];
+        let solution = solve(&matrix, &[8400, 5400]);
+        assert_eq!(solution, unique(&[(80, 1), (40, 1)]));
+        assert_eq!(solution.integers(), Some(vec![80, 40]));
+    }
+
+    #[test]
+    fn solves_fractional_system() {
+        let matrix = vec![vec![2, 1, -1], vec![-3, -1, 2], vec![-2, 1, 2]];
+        assert_eq!(
+            solve(&matrix, &[8, -11, -3]),
+            unique(&[(2, 1), (3, 1), (-1, 1)])
+        );
+
+        let solution = solve(&[vec![2, 0], vec![0, 3]], &[1, 1]);
+        assert_eq!(solution, unique(&[(1, 2), (1, 3)]));
+        assert_eq!(solution.integers(), None);
+    }
+
+    #[test]
+    fn pivots_around_zeros() {
+        let matrix = vec![vec![0, 1, 0], vec![0, 0, 1], vec![1, 0, 0]];
+        assert_eq!(
+            solve(&matrix, &[5, 6, 7]),
+            unique(&[(7, 1), (5, 1), (6, 1)])
+        );
+    }
+
+    #[test]
+    fn detects_inconsistent_system() {
+        assert_eq!(solve(&[vec![1, 2], vec![2, 4]], &[3, 7]), Solution::None);
+        assert_eq!(solve(&[vec![0, 0], vec![0, 0]], &[0, 1]), Solution::None);
+    }
+
+    #[test]
+    fn detects_underdetermined_system() {
+        assert_eq!(
+            solve(&[vec![1, 2], vec![2, 4]], &[3, 6]),
+            Solution::Infinite
+        );
+        let matrix = vec![vec![1, 1, 1], vec![1, 2, 3], vec![2, 3, 4]];
+        assert_eq!(solve(&matrix, &[6, 14, 20]), Solution::Infinite);
+    }
+
+    #[test]
+    fn solves_rational_system() {
+        let half = Rational::new(1, 2);
+        let third = Rational::new(1, 3);
+        let one = Rational::from_integer(1);
+        let matrix = vec![vec![half, third], vec![one, -one]];
+        let solution = solve_rational(&matrix, &[Rational::from_integer(2), one]);
+        assert_eq!(solution, unique(&[(14, 5), (9, 5)]));
+    }
+
+    #[test]
+    fn solves_empty_system() {
+        assert_eq!(solve(&[], &[]), Solution::Unique(vec![]));
+    }
+}
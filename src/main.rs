@@ -7,7 +7,7 @@ use advent_of_code::template::Day;
 use std::process;
 
 mod args {
-    use advent_of_code::template::Day;
+    use advent_of_code::template::{commands::solve::Visualize, Day};
     use std::process;
 
     pub enum AppArguments {
@@ -27,6 +27,9 @@ mod args {
             release: bool,
             dhat: bool,
             submit: Option<u8>,
+            variant: Option<String>,
+            explain: bool,
+            visualize: Option<Visualize>,
         },
         All {
             release: bool,
@@ -35,6 +38,7 @@ mod args {
             all: bool,
             day: Option<Day>,
             store: bool,
+            variants: bool,
         },
         #[cfg(feature = "today")]
         Today,
@@ -50,11 +54,13 @@ mod args {
             Some("time") => {
                 let all = args.contains("--all");
                 let store = args.contains("--store");
+                let variants = args.contains("--variants");
 
                 AppArguments::Time {
                     all,
                     day: args.opt_free_from_str()?,
                     store,
+                    variants,
                 }
             }
             Some("download") => AppArguments::Download {
@@ -73,6 +79,25 @@ mod args {
                 release: args.contains("--release"),
                 submit: args.opt_value_from_str("--submit")?,
                 dhat: args.contains("--dhat"),
+                variant: args.opt_value_from_str("--variant")?,
+                explain: args.contains("--explain"),
+                visualize: {
+                    let visualize = args.contains("--visualize");
+                    let frames: Option<String> = args.opt_value_from_str("--frames")?;
+                    if visualize || frames.is_some() {
+                        Some(Visualize {
+                            fps: args.opt_value_from_str("--fps")?,
+                            jump: args.opt_value_from_str("--jump")?,
+                            paused: args.contains("--paused"),
+                            frames,
+                            format: args.opt_value_from_str("--format")?,
+                            cell_size: args.opt_value_from_str("--cell-size")?,
+                            palette: args.opt_value_from_str("--palette")?,
+                        })
+                    } else {
+                        None
+                    }
+                },
             },
             #[cfg(feature = "today")]
             Some("today") => AppArguments::Today,
@@ -103,7 +128,12 @@ fn main() {
         }
         Ok(args) => match args {
             AppArguments::All { release } => all::handle(release),
-            AppArguments::Time { day, all, store } => time::handle(day, all, store),
+            AppArguments::Time {
+                day,
+                all,
+                store,
+                variants,
+            } => time::handle(day, all, store, variants),
             AppArguments::Download { day } => download::handle(day),
             AppArguments::Read { day } => read::handle(day),
             AppArguments::Scaffold {
@@ -121,7 +151,10 @@ fn main() {
                 release,
                 dhat,
                 submit,
-            } => solve::handle(day, release, dhat, submit),
+                variant,
+                explain,
+                visualize,
+            } => solve::handle(day, release, dhat, submit, variant, explain, visualize),
             #[cfg(feature = "today")]
             AppArguments::Today => {
                 match Day::today() {
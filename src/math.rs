@@ -0,0 +1,251 @@
+//! Number theory helpers: gcd/lcm, extended Euclid, modular inverses and the Chinese remainder theorem.
+use std::ops::{Add, Div, Mul, Neg, Rem, Sub};
+
+/// Primitive integer types the helpers in this module are generic over.
+pub trait Integer:
+    Copy
+    + Ord
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Rem<Output = Self>
+{
+    const ZERO: Self;
+    const ONE: Self;
+}
+
+/// Signed primitive integer types, needed wherever Bézout coefficients can be negative.
+pub trait SignedInteger: Integer + Neg<Output = Self> {}
+
+macro_rules! impl_integer {
+    ($($t:ty),*) => {
+        $(
+            impl Integer for $t {
+                const ZERO: Self = 0;
+                const ONE: Self = 1;
+            }
+        )*
+    };
+}
+
+macro_rules! impl_signed_integer {
+    ($($t:ty),*) => {
+        impl_integer!($($t),*);
+        $( impl SignedInteger for $t {} )*
+    };
+}
+
+impl_integer!(u8, u16, u32, u64, u128, usize);
+impl_signed_integer!(i8, i16, i32, i64, i128, isize);
+
+/// Greatest common divisor, always non-negative. `gcd(0, 0)` is 0.
+pub fn gcd<T: Integer>(mut a: T, mut b: T) -> T {
+    while b != T::ZERO {
+        (a, b) = (b, a % b);
+    }
+    if a < T::ZERO {
+        T::ZERO - a
+    } else {
+        a
+    }
+}
+
+/// Least common multiple, always non-negative. Panics on overflow in debug builds.
+pub fn lcm<T: Integer>(a: T, b: T) -> T {
+    if a == T::ZERO || b == T::ZERO {
+        return T::ZERO;
+    }
+    let lcm = a / gcd(a, b) * b;
+    if lcm < T::ZERO {
+        T::ZERO - lcm
+    } else {
+        lcm
+    }
+}
+
+/// Least common multiple of all values. An empty iterator yields 1.
+pub fn lcm_all<T: Integer>(values: impl IntoIterator<Item = T>) -> T {
+    values.into_iter().fold(T::ONE, lcm)
+}
+
+/// Returns `(g, x, y)` such that `a * x + b * y == g == gcd(a, b)`.
+pub fn extended_gcd<T: SignedInteger>(a: T, b: T) -> (T, T, T) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (T::ONE, T::ZERO);
+    let (mut old_y, mut y) = (T::ZERO, T::ONE);
+    while r != T::ZERO {
+        let quotient = old_r / r;
+        (old_r, r) = (r, old_r - quotient * r);
+        (old_x, x) = (x, old_x - quotient * x);
+        (old_y, y) = (y, old_y - quotient * y);
+    }
+    if old_r < T::ZERO {
+        (-old_r, -old_x, -old_y)
+    } else {
+        (old_r, old_x, old_y)
+    }
+}
+
+/// Non-negative remainder of `a` divided by a positive `modulus`.
+pub fn rem_euclid<T: Integer>(a: T, modulus: T) -> T {
+    let r = a % modulus;
+    if r < T::ZERO {
+        r + modulus
+    } else {
+        r
+    }
+}
+
+/// The `x` in `0..modulus` with `a * x ≡ 1 (mod modulus)`, or `None` if `a` and `modulus` are not coprime.
+pub fn mod_inverse<T: SignedInteger>(a: T, modulus: T) -> Option<T> {
+    let (g, x, _) = extended_gcd(rem_euclid(a, modulus), modulus);
+    (g == T::ONE).then(|| rem_euclid(x, modulus))
+}
+
+/// A congruence `x ≡ residue (mod modulus)` with the residue in `0..modulus`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Congruence {
+    pub residue: u64,
+    pub modulus: u64,
+}
+
+impl Congruence {
+    /// Creates a congruence from any residue, e.g. a negative difference of offsets.
+    pub fn new(residue: i64, modulus: u64) -> Self {
+        assert!(modulus > 0, "moduli must be positive");
+        let residue = rem_euclid(i128::from(residue), i128::from(modulus));
+        Self {
+            residue: residue.try_into().expect("Residue is below a u64 modulus"),
+            modulus,
+        }
+    }
+}
+
+/// Solves a system of congruences with the Chinese remainder theorem.
+///
+/// The moduli do not have to be coprime. Returns the combined congruence, whose modulus is the
+/// lcm of all moduli, or `None` if the congruences contradict each other or the combined modulus
+/// does not fit into a `u64`.
+/// An empty system yields `x ≡ 0 (mod 1)`.
+pub fn crt(congruences: impl IntoIterator<Item = Congruence>) -> Option<Congruence> {
+    // the combined modulus is checked to stay below 2^64 before it is used, so residues and
+    // moduli, their sums and `modulus * k` with `k` below the reduced modulus fit into an i128.
+    let mut residue: i128 = 0;
+    let mut modulus: i128 = 1;
+    for congruence in congruences {
+        let other_modulus = i128::from(congruence.modulus);
+        let other_residue = i128::from(congruence.residue) % other_modulus;
+
+        // residue + modulus * k ≡ other_residue (mod other_modulus)
+        let (g, inverse, _) = extended_gcd(modulus, other_modulus);
+        let difference = other_residue - residue;
+        if difference % g != 0 {
+            return None;
+        }
+        let reduced_modulus = other_modulus / g;
+        // both factors may be just below 2^64, so their product can overflow even an i128.
+        let combined_modulus = modulus
+            .checked_mul(reduced_modulus)
+            .filter(|&combined| combined <= i128::from(u64::MAX))?;
+        // both factors are below 2^64, so their product only fits into a u128.
+        let k = (rem_euclid(difference / g, reduced_modulus) as u128
+            * rem_euclid(inverse, reduced_modulus) as u128
+            % reduced_modulus as u128) as i128;
+        residue = rem_euclid(residue + modulus * k, combined_modulus);
+        modulus = combined_modulus;
+    }
+    Some(Congruence {
+        residue: residue.try_into().ok()?,
+        modulus: modulus.try_into().ok()?,
+    })
+}
+
+#[cfg(feature = "test_lib")]
+mod tests {
+    use super::{crt, extended_gcd, gcd, lcm, lcm_all, mod_inverse, Congruence};
+
+    fn congruence(residue: i64, modulus: u64) -> Congruence {
+        Congruence::new(residue, modulus)
+    }
+
+    #[test]
+    fn computes_gcd_and_lcm() {
+        assert_eq!(gcd(12_u32, 18), 6);
+        assert_eq!(gcd(-12_i64, 18), 6);
+        assert_eq!(gcd(0_u8, 0), 0);
+        assert_eq!(lcm(4_u64, 6), 12);
+        assert_eq!(lcm(-4_i32, 6), 12);
+        assert_eq!(lcm_all([2_u64, 3, 4, 5]), 60);
+        assert_eq!(lcm_all(Vec::<u32>::new()), 1);
+    }
+
+    #[test]
+    fn computes_bezout_coefficients() {
+        let (g, x, y) = extended_gcd(240_i64, 46);
+        assert_eq!(g, 2);
+        assert_eq!(240 * x + 46 * y, 2);
+        let (g, x, y) = extended_gcd(-15_i32, 10);
+        assert_eq!(g, 5);
+        assert_eq!(-15 * x + 10 * y, 5);
+    }
+
+    #[test]
+    fn computes_modular_inverse() {
+        assert_eq!(mod_inverse(3_i64, 11), Some(4));
+        assert_eq!(mod_inverse(-3_i64, 11), Some(7));
+        assert_eq!(mod_inverse(6_i32, 9), None);
+        assert_eq!(mod_inverse(103_i64, 101), Some(51));
+    }
+
+    #[test]
+    fn solves_coprime_congruences() {
+        let result = crt([congruence(2, 3), congruence(3, 5), congruence(2, 7)]);
+        assert_eq!(result, Some(congruence(23, 105)));
+    }
+
+    #[test]
+    fn solves_non_coprime_congruences() {
+        let result = crt([congruence(2, 4), congruence(4, 6)]);
+        assert_eq!(result, Some(congruence(10, 12)));
+    }
+
+    #[test]
+    fn handles_negative_residues() {
+        let result = crt([congruence(-1, 101), congruence(-1, 103)]);
+        assert_eq!(result, Some(congruence(101 * 103 - 1, 101 * 103)));
+    }
+
+    #[test]
+    fn rejects_inconsistent_congruences() {
+        assert_eq!(crt([congruence(1, 4), congruence(2, 6)]), None);
+    }
+
+    #[test]
+    fn handles_large_moduli() {
+        let a = 4_294_967_291; // largest prime below 2^32
+        let b = 4_294_967_279;
+        let result = crt([congruence(a as i64 - 1, a), congruence(5, b)]).unwrap();
+        assert_eq!(result.modulus, a * b);
+        assert_eq!(result.residue % a, a - 1);
+        assert_eq!(result.residue % b, 5);
+    }
+
+    #[test]
+    fn handles_moduli_near_u64_max() {
+        let p = 18_446_744_073_709_551_557; // largest prime below 2^64
+        let result = crt([congruence(-2, p), congruence(0, 1)]);
+        assert_eq!(result, Some(congruence(-2, p)));
+        assert_eq!(result.unwrap().residue, p - 2);
+        // the combined modulus 2p does not fit into a u64
+        assert_eq!(crt([congruence(-2, p), congruence(1, 2)]), None);
+        // coprime moduli whose product is close to 2^128
+        let result = crt([congruence(1, u64::MAX), congruence(2, u64::MAX - 1)]);
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn handles_empty_system() {
+        assert_eq!(crt([]), Some(congruence(0, 1)));
+    }
+}
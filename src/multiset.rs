@@ -0,0 +1,186 @@
+//! Repeated transformation of a multiset in which every element independently turns into zero or
+//! more new elements.
+//!
+//! Only the number of copies of each distinct element is stored, so the cost of a step depends on
+//! how many distinct elements there are rather than on the total count, which can grow
+//! exponentially. Counts are generic so deep transformations can use `u128` or `BigUint`.
+use num_traits::CheckedAdd;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::iter;
+
+/// A number of copies of an element, e.g. `u64`, `u128` or `BigUint`.
+pub trait Count: Clone + Default + From<u64> + CheckedAdd {}
+
+impl<C: Clone + Default + From<u64> + CheckedAdd> Count for C {}
+
+/// Adds two counts, panicking rather than wrapping around if the count type is too small.
+pub fn add<C: Count>(a: &C, b: &C) -> C {
+    a.checked_add(b)
+        .expect("Count overflowed; use a wider count type")
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Multiset<T: Eq + Hash, C> {
+    counts: HashMap<T, C>,
+}
+
+impl<T: Eq + Hash, C: Count> Multiset<T, C> {
+    pub fn new() -> Self {
+        Self {
+            counts: HashMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, element: T, count: &C) {
+        let total = self.counts.entry(element).or_default();
+        *total = add(total, count);
+    }
+
+    /// The number of distinct elements.
+    pub fn distinct(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// The total number of elements, counting every copy.
+    pub fn total(&self) -> C {
+        self.counts
+            .values()
+            .fold(C::default(), |total, count| add(&total, count))
+    }
+
+    pub fn count(&self, element: &T) -> C {
+        self.counts.get(element).cloned().unwrap_or_default()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&T, &C)> {
+        self.counts.iter()
+    }
+}
+
+impl<T: Eq + Hash, C: Count> Default for Multiset<T, C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Eq + Hash, C: Count> FromIterator<T> for Multiset<T, C> {
+    fn from_iter<I: IntoIterator<Item = T>>(elements: I) -> Self {
+        let one = C::from(1);
+        let mut multiset = Self::new();
+        for element in elements {
+            multiset.insert(element, &one);
+        }
+        multiset
+    }
+}
+
+/// Applies `rule` to every element of a multiset, remembering the result for each distinct
+/// element so the rule runs once per element however many steps it appears in.
+pub struct Transformer<T, F> {
+    rule: F,
+    cache: HashMap<T, Vec<T>>,
+}
+
+impl<T: Eq + Hash + Clone, F: FnMut(&T) -> Vec<T>> Transformer<T, F> {
+    pub fn new(rule: F) -> Self {
+        Self {
+            rule,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Replaces every element with the elements the rule turns it into.
+    pub fn step<C: Count>(&mut self, multiset: &Multiset<T, C>) -> Multiset<T, C> {
+        let mut next = Multiset::new();
+        for (element, count) in multiset.iter() {
+            let outputs = self
+                .cache
+                .entry(element.clone())
+                .or_insert_with(|| (self.rule)(element));
+            for output in outputs.iter() {
+                next.insert(output.clone(), count);
+            }
+        }
+        next
+    }
+
+    /// The multiset after `steps` steps.
+    pub fn run<C: Count>(&mut self, mut multiset: Multiset<T, C>, steps: usize) -> Multiset<T, C> {
+        for _ in 0..steps {
+            multiset = self.step(&multiset);
+        }
+        multiset
+    }
+
+    /// Every multiset from the given one onwards, one step apart. Each step is computed as the
+    /// previous multiset is returned.
+    pub fn steps<'a, C: Count + 'a>(
+        &'a mut self,
+        multiset: Multiset<T, C>,
+    ) -> impl Iterator<Item = Multiset<T, C>> + 'a {
+        iter::successors(Some(multiset), move |multiset| Some(self.step(multiset)))
+    }
+}
+
+#[cfg(feature = "test_lib")]
+mod tests {
+    use super::{Multiset, Transformer};
+    use num_bigint::BigUint;
+
+    /// Each cell of a lanternfish timer spawns a new fish at 8 when it reaches 0.
+    fn lanternfish(timer: &u8) -> Vec<u8> {
+        match timer {
+            0 => vec![6, 8],
+            _ => vec![timer - 1],
+        }
+    }
+
+    #[test]
+    fn counts_copies() {
+        let multiset: Multiset<char, u64> = "abracadabra".chars().collect();
+        assert_eq!(multiset.distinct(), 5);
+        assert_eq!(multiset.total(), 11);
+        assert_eq!(multiset.count(&'a'), 5);
+        assert_eq!(multiset.count(&'z'), 0);
+    }
+
+    #[test]
+    fn transforms_every_copy() {
+        let mut transformer = Transformer::new(lanternfish);
+        let fish: Multiset<u8, u64> = [3, 4, 3, 1, 2].into_iter().collect();
+        assert_eq!(transformer.run(fish.clone(), 18).total(), 26);
+        assert_eq!(transformer.run(fish, 80).total(), 5934);
+    }
+
+    #[test]
+    fn exposes_every_step() {
+        let mut transformer = Transformer::new(|&n: &u32| vec![n / 2, n / 3]);
+        let start: Multiset<u32, u64> = [100].into_iter().collect();
+        let distinct: Vec<usize> = transformer
+            .steps(start)
+            .take(5)
+            .map(|multiset| multiset.distinct())
+            .collect();
+        assert_eq!(distinct, [1, 2, 3, 4, 4]);
+    }
+
+    #[test]
+    fn supports_big_counts() {
+        let mut transformer = Transformer::new(|&n: &u8| vec![n, n]);
+        let start: Multiset<u8, BigUint> = [0].into_iter().collect();
+        let doubled = transformer.run(start, 200);
+        assert_eq!(doubled.total(), BigUint::from(1u8) << 200);
+
+        let start: Multiset<u8, u128> = [0].into_iter().collect();
+        assert_eq!(transformer.run(start, 127).total(), 1 << 127);
+    }
+
+    #[test]
+    #[should_panic(expected = "Count overflowed")]
+    fn panics_on_overflow() {
+        let mut transformer = Transformer::new(|&n: &u8| vec![n, n]);
+        let start: Multiset<u8, u64> = [0].into_iter().collect();
+        transformer.run(start, 64);
+    }
+}
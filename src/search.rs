@@ -0,0 +1,395 @@
+//! Generic graph searches over a neighbour closure.
+//!
+//! Nodes are any hashable, cloneable values (grid points, `(point, direction)` states, ...).
+//! The graph is never materialised: each search calls `neighbours(&node)` when it expands a node.
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+use std::ops::Add;
+
+/// The result of a single-source (or multi-source) shortest path search.
+#[derive(Debug, Clone)]
+pub struct Search<N, C> {
+    /// Shortest distance from the nearest start to every reached node.
+    pub distances: HashMap<N, C>,
+    /// Every predecessor of a node that lies on one of its shortest paths. Starts have none.
+    pub predecessors: HashMap<N, Vec<N>>,
+    /// Number of distinct shortest paths from the starts to every reached node.
+    pub path_counts: HashMap<N, u64>,
+}
+
+impl<N: Eq + Hash + Clone, C: Copy> Search<N, C> {
+    fn new() -> Self {
+        Self {
+            distances: HashMap::new(),
+            predecessors: HashMap::new(),
+            path_counts: HashMap::new(),
+        }
+    }
+
+    fn add_start(&mut self, start: N, zero: C) {
+        self.distances.insert(start.clone(), zero);
+        self.predecessors.insert(start.clone(), vec![]);
+        self.path_counts.insert(start, 1);
+    }
+
+    pub fn distance(&self, node: &N) -> Option<C> {
+        self.distances.get(node).copied()
+    }
+
+    pub fn contains(&self, node: &N) -> bool {
+        self.distances.contains_key(node)
+    }
+
+    /// One shortest path from a start to `target`, both included.
+    pub fn path_to(&self, target: &N) -> Option<Vec<N>> {
+        let mut node = target;
+        let mut path = vec![node.clone()];
+        while let Some(predecessor) = self.predecessors.get(node)?.first() {
+            path.push(predecessor.clone());
+            node = predecessor;
+        }
+        path.reverse();
+        Some(path)
+    }
+}
+
+/// Breadth-first search with unit edge costs.
+pub fn bfs<N, I>(
+    starts: impl IntoIterator<Item = N>,
+    mut neighbours: impl FnMut(&N) -> I,
+) -> Search<N, usize>
+where
+    N: Eq + Hash + Clone,
+    I: IntoIterator<Item = N>,
+{
+    let mut search = Search::new();
+    let mut queue = VecDeque::new();
+    for start in starts {
+        if !search.contains(&start) {
+            search.add_start(start.clone(), 0);
+            queue.push_back(start);
+        }
+    }
+
+    while let Some(node) = queue.pop_front() {
+        let distance = search.distances[&node] + 1;
+        let count = search.path_counts[&node];
+        for neighbour in neighbours(&node) {
+            match search.distances.get(&neighbour) {
+                None => {
+                    search.distances.insert(neighbour.clone(), distance);
+                    search
+                        .predecessors
+                        .insert(neighbour.clone(), vec![node.clone()]);
+                    search.path_counts.insert(neighbour.clone(), count);
+                    queue.push_back(neighbour);
+                }
+                Some(&existing) if existing == distance => {
+                    search
+                        .predecessors
+                        .get_mut(&neighbour)
+                        .expect("Reached nodes have predecessors")
+                        .push(node.clone());
+                    *search
+                        .path_counts
+                        .get_mut(&neighbour)
+                        .expect("Reached nodes have path counts") += count;
+                }
+                Some(_) => (),
+            }
+        }
+    }
+
+    search
+}
+
+/// Depth-first search returning every node reachable from `start` in pre-order.
+pub fn dfs<N, I>(start: N, mut neighbours: impl FnMut(&N) -> I) -> Vec<N>
+where
+    N: Eq + Hash + Clone,
+    I: IntoIterator<Item = N>,
+{
+    let mut visited = HashSet::new();
+    let mut order = vec![];
+    let mut stack = vec![start];
+    while let Some(node) = stack.pop() {
+        if !visited.insert(node.clone()) {
+            continue;
+        }
+        let mut next: Vec<N> = neighbours(&node)
+            .into_iter()
+            .filter(|neighbour| !visited.contains(neighbour))
+            .collect();
+        // push in reverse so the first neighbour is explored first.
+        next.reverse();
+        stack.extend(next);
+        order.push(node);
+    }
+    order
+}
+
+/// Every node reachable from `start`, e.g. a region of connected grid cells.
+pub fn flood_fill<N, I>(start: N, mut neighbours: impl FnMut(&N) -> I) -> HashSet<N>
+where
+    N: Eq + Hash + Clone,
+    I: IntoIterator<Item = N>,
+{
+    let mut region = HashSet::from([start.clone()]);
+    let mut stack = vec![start];
+    while let Some(node) = stack.pop() {
+        for neighbour in neighbours(&node) {
+            if region.insert(neighbour.clone()) {
+                stack.push(neighbour);
+            }
+        }
+    }
+    region
+}
+
+/// Heap entry ordered by lowest cost first, ignoring the node.
+struct State<N, C> {
+    cost: C,
+    node: N,
+}
+
+impl<N, C: Ord> PartialEq for State<N, C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost == other.cost
+    }
+}
+
+impl<N, C: Ord> Eq for State<N, C> {}
+
+impl<N, C: Ord> PartialOrd for State<N, C> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<N, C: Ord> Ord for State<N, C> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.cmp(&self.cost)
+    }
+}
+
+/// Dijkstra's algorithm for non-negative edge costs. `C::default()` must be the zero cost.
+///
+/// Path counts are only exact when every edge cost is strictly positive.
+pub fn dijkstra<N, C, I>(
+    starts: impl IntoIterator<Item = N>,
+    mut neighbours: impl FnMut(&N) -> I,
+) -> Search<N, C>
+where
+    N: Eq + Hash + Clone,
+    C: Copy + Ord + Default + Add<Output = C>,
+    I: IntoIterator<Item = (N, C)>,
+{
+    let mut search = Search::new();
+    let mut heap = BinaryHeap::new();
+    for start in starts {
+        if !search.contains(&start) {
+            search.add_start(start.clone(), C::default());
+            heap.push(State {
+                cost: C::default(),
+                node: start,
+            });
+        }
+    }
+
+    let mut settled = HashSet::new();
+    while let Some(State { cost, node }) = heap.pop() {
+        if !settled.insert(node.clone()) {
+            continue;
+        }
+        let count = search.path_counts[&node];
+        for (neighbour, edge_cost) in neighbours(&node) {
+            let next_cost = cost + edge_cost;
+            match search.distances.get(&neighbour) {
+                Some(&existing) if existing < next_cost => (),
+                Some(&existing) if existing == next_cost => {
+                    search
+                        .predecessors
+                        .get_mut(&neighbour)
+                        .expect("Reached nodes have predecessors")
+                        .push(node.clone());
+                    *search
+                        .path_counts
+                        .get_mut(&neighbour)
+                        .expect("Reached nodes have path counts") += count;
+                }
+                _ => {
+                    search.distances.insert(neighbour.clone(), next_cost);
+                    search
+                        .predecessors
+                        .insert(neighbour.clone(), vec![node.clone()]);
+                    search.path_counts.insert(neighbour.clone(), count);
+                    heap.push(State {
+                        cost: next_cost,
+                        node: neighbour,
+                    });
+                }
+            }
+        }
+    }
+
+    search
+}
+
+/// A* search from `start` to the first node satisfying `is_goal`.
+/// Returns the path (both ends included) and its cost.
+///
+/// `heuristic` must never overestimate the remaining cost, otherwise the path may not be the shortest.
+pub fn astar<N, C, I>(
+    start: N,
+    mut neighbours: impl FnMut(&N) -> I,
+    mut heuristic: impl FnMut(&N) -> C,
+    mut is_goal: impl FnMut(&N) -> bool,
+) -> Option<(Vec<N>, C)>
+where
+    N: Eq + Hash + Clone,
+    C: Copy + Ord + Default + Add<Output = C>,
+    I: IntoIterator<Item = (N, C)>,
+{
+    let mut costs = HashMap::from([(start.clone(), C::default())]);
+    let mut came_from: HashMap<N, N> = HashMap::new();
+    let mut heap = BinaryHeap::from([State {
+        cost: heuristic(&start),
+        node: start,
+    }]);
+
+    while let Some(State { node, .. }) = heap.pop() {
+        let cost = costs[&node];
+        if is_goal(&node) {
+            let mut path = vec![node.clone()];
+            let mut current = &node;
+            while let Some(previous) = came_from.get(current) {
+                path.push(previous.clone());
+                current = previous;
+            }
+            path.reverse();
+            return Some((path, cost));
+        }
+        for (neighbour, edge_cost) in neighbours(&node) {
+            let next_cost = cost + edge_cost;
+            if costs
+                .get(&neighbour)
+                .is_some_and(|&existing| existing <= next_cost)
+            {
+                continue;
+            }
+            costs.insert(neighbour.clone(), next_cost);
+            came_from.insert(neighbour.clone(), node.clone());
+            heap.push(State {
+                cost: next_cost + heuristic(&neighbour),
+                node: neighbour,
+            });
+        }
+    }
+
+    None
+}
+
+#[cfg(feature = "test_lib")]
+mod tests {
+    use super::{astar, bfs, dfs, dijkstra, flood_fill};
+
+    const GRID: &str = "\
+..#.
+..#.
+....";
+
+    fn open_neighbours((x, y): &(i32, i32)) -> Vec<(i32, i32)> {
+        let rows: Vec<&[u8]> = GRID.lines().map(str::as_bytes).collect();
+        [(0, 1), (1, 0), (0, -1), (-1, 0)]
+            .into_iter()
+            .map(|(dx, dy)| (x + dx, y + dy))
+            .filter(|&(nx, ny)| {
+                ny >= 0
+                    && nx >= 0
+                    && (ny as usize) < rows.len()
+                    && (nx as usize) < rows[0].len()
+                    && rows[ny as usize][nx as usize] == b'.'
+            })
+            .collect()
+    }
+
+    #[test]
+    fn bfs_finds_distances_and_paths() {
+        let search = bfs([(0, 0)], open_neighbours);
+        assert_eq!(search.distance(&(3, 0)), Some(7));
+        assert_eq!(search.distance(&(2, 0)), None);
+        let path = search.path_to(&(3, 0)).unwrap();
+        assert_eq!(path.first(), Some(&(0, 0)));
+        assert_eq!(path.last(), Some(&(3, 0)));
+        assert_eq!(path.len(), 8);
+    }
+
+    #[test]
+    fn bfs_counts_shortest_paths() {
+        // on an open 3x3 grid there are 6 shortest paths between opposite corners.
+        let search = bfs([(0, 0)], |&(x, y): &(i32, i32)| {
+            [(x + 1, y), (x, y + 1)]
+                .into_iter()
+                .filter(|&(nx, ny)| nx <= 2 && ny <= 2)
+        });
+        assert_eq!(search.path_counts[&(2, 2)], 6);
+        assert_eq!(search.predecessors[&(2, 2)].len(), 2);
+    }
+
+    #[test]
+    fn bfs_supports_multiple_starts() {
+        let search = bfs([(0, 0), (3, 2)], open_neighbours);
+        assert_eq!(search.distance(&(3, 0)), Some(2));
+        assert_eq!(search.distance(&(0, 2)), Some(2));
+    }
+
+    #[test]
+    fn dfs_visits_reachable_nodes_once() {
+        let order = dfs((0, 0), open_neighbours);
+        assert_eq!(order.len(), 10);
+        assert_eq!(order[0], (0, 0));
+    }
+
+    #[test]
+    fn flood_fill_finds_region() {
+        let region = flood_fill(0, |&n: &u32| {
+            [n + 1, n.saturating_sub(1)].into_iter().filter(|&m| m < 5)
+        });
+        assert_eq!(region.len(), 5);
+    }
+
+    #[test]
+    fn dijkstra_prefers_cheaper_route() {
+        let edges = |&n: &u8| match n {
+            0 => vec![(1, 10), (2, 1)],
+            2 => vec![(1, 2)],
+            1 => vec![(3, 1)],
+            _ => vec![],
+        };
+        let search = dijkstra([0_u8], edges);
+        assert_eq!(search.distance(&1), Some(3_u32));
+        assert_eq!(search.path_to(&3), Some(vec![0, 2, 1, 3]));
+        assert_eq!(search.path_counts[&3], 1);
+    }
+
+    #[test]
+    fn astar_matches_bfs_distance() {
+        let goal = (3, 0);
+        let (path, cost) = astar(
+            (0, 0),
+            |node| open_neighbours(node).into_iter().map(|n| (n, 1_u32)),
+            |&(x, y)| (goal.0 - x).unsigned_abs() + (goal.1 - y).unsigned_abs(),
+            |&node| node == goal,
+        )
+        .unwrap();
+        assert_eq!(cost, 7);
+        assert_eq!(path.len(), 8);
+    }
+
+    #[test]
+    fn astar_returns_none_when_unreachable() {
+        let result = astar(0_u8, |_| vec![], |_| 0_u32, |&n| n == 1);
+        assert_eq!(result.is_none(), true);
+    }
+}
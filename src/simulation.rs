@@ -0,0 +1,423 @@
+//! Step-by-step simulations, a terminal player that animates them and export of their frames
+//! as images.
+//!
+//! The player redraws each frame in place. When stdin is a terminal it switches it to raw mode
+//! (no line buffering, echo or signals) and reads single key presses:
+//!
+//! - `space`: pause or resume
+//! - `n`: advance a single step (pauses playback)
+//! - `+` / `-`: double or halve the frame rate
+//! - digits followed by `g` or `enter`: jump forward to that step
+//! - `q`, `esc` or `ctrl-c`: stop playback
+use std::env;
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::image::{Format, FrameWriter, Image, Palette};
+use crate::template::{ANSI_BOLD, ANSI_RESET};
+
+const CLEAR_SCREEN: &str = "\x1b[2J";
+const CURSOR_HOME: &str = "\x1b[H";
+const CLEAR_LINE: &str = "\x1b[K";
+const CLEAR_BELOW: &str = "\x1b[J";
+const HIDE_CURSOR: &str = "\x1b[?25l";
+const SHOW_CURSOR: &str = "\x1b[?25h";
+
+pub trait Simulation {
+    /// Advances the simulation by one step. Only called while `is_done` is false.
+    fn step(&mut self);
+
+    /// Draws the current state, one line per row.
+    fn render(&self) -> String;
+
+    fn is_done(&self) -> bool;
+}
+
+/// Plays a [`Simulation`] in the terminal.
+#[derive(Debug, Clone)]
+pub struct Player {
+    fps: f64,
+    paused: bool,
+    jump_to: usize,
+}
+
+impl Default for Player {
+    fn default() -> Self {
+        Self {
+            fps: 30.0,
+            paused: false,
+            jump_to: 0,
+        }
+    }
+}
+
+enum Key {
+    TogglePause,
+    Step,
+    Faster,
+    Slower,
+    Digit(char),
+    Jump,
+    Quit,
+    Other,
+}
+
+impl From<u8> for Key {
+    fn from(byte: u8) -> Self {
+        match byte {
+            b' ' => Key::TogglePause,
+            b'n' => Key::Step,
+            b'+' | b'=' => Key::Faster,
+            b'-' => Key::Slower,
+            b'0'..=b'9' => Key::Digit(byte.into()),
+            b'g' | b'\n' | b'\r' => Key::Jump,
+            b'q' | 0x1b | 0x03 => Key::Quit,
+            _ => Key::Other,
+        }
+    }
+}
+
+impl Player {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads `--fps <n>`, `--jump <step>` and `--paused` from the command line.
+    pub fn from_args() -> Self {
+        let args: Vec<String> = env::args().collect();
+        let mut player = Self::new().paused(args.iter().any(|x| x == "--paused"));
+        if let Some(fps) = arg_value(&args, "--fps") {
+            player = player.fps(fps);
+        }
+        if let Some(step) = arg_value(&args, "--jump") {
+            player = player.jump_to(step);
+        }
+        player
+    }
+
+    pub fn fps(mut self, fps: f64) -> Self {
+        assert!(fps > 0.0, "fps must be positive");
+        self.fps = fps;
+        self
+    }
+
+    /// Starts paused. Ignored if there is no terminal to read keys from.
+    pub fn paused(mut self, paused: bool) -> Self {
+        self.paused = paused;
+        self
+    }
+
+    /// Skips ahead to `step` before drawing the first frame.
+    pub fn jump_to(mut self, step: usize) -> Self {
+        self.jump_to = step;
+        self
+    }
+
+    /// Plays the simulation until it is done or playback is stopped.
+    /// Returns the number of steps taken.
+    pub fn play<S: Simulation>(&self, simulation: &mut S) -> io::Result<usize> {
+        let raw_mode = RawMode::enable();
+        let keys = raw_mode.as_ref().map(|_| read_keys());
+        let steps = self.run(simulation, &mut io::stdout().lock(), keys);
+        drop(raw_mode);
+        steps
+    }
+
+    fn run<S: Simulation>(
+        &self,
+        simulation: &mut S,
+        out: &mut impl Write,
+        mut keys: Option<Receiver<u8>>,
+    ) -> io::Result<usize> {
+        let mut fps = self.fps;
+        let mut paused = self.paused && keys.is_some();
+        let mut target = self.jump_to;
+        let mut steps = 0;
+        let mut typed = String::new();
+
+        write!(out, "{HIDE_CURSOR}{CLEAR_SCREEN}")?;
+        loop {
+            while steps < target && !simulation.is_done() {
+                simulation.step();
+                steps += 1;
+            }
+            let status = if simulation.is_done() {
+                format!("done after {steps} steps")
+            } else if paused {
+                format!("step {steps} | paused | {typed}")
+            } else {
+                format!("step {steps} | {fps} fps | {typed}")
+            };
+            draw(out, &simulation.render(), &status)?;
+            if simulation.is_done() {
+                break;
+            }
+
+            let deadline = Instant::now() + Duration::from_secs_f64(1.0 / fps);
+            let key = match &keys {
+                Some(receiver) if paused => {
+                    receiver.recv().map_err(|_| RecvTimeoutError::Disconnected)
+                }
+                Some(receiver) => receiver.recv_timeout(deadline - Instant::now()),
+                None => {
+                    thread::sleep(deadline - Instant::now());
+                    Err(RecvTimeoutError::Timeout)
+                }
+            };
+            match key.map(Key::from) {
+                Err(RecvTimeoutError::Timeout) => target = steps + 1,
+                Err(RecvTimeoutError::Disconnected) => {
+                    // stdin was closed, keep playing without keys
+                    keys = None;
+                    paused = false;
+                }
+                Ok(Key::TogglePause) => paused = !paused,
+                Ok(Key::Step) => {
+                    paused = true;
+                    target = steps + 1;
+                }
+                Ok(Key::Faster) => fps *= 2.0,
+                Ok(Key::Slower) => fps /= 2.0,
+                Ok(Key::Digit(digit)) => typed.push(digit),
+                Ok(Key::Jump) => {
+                    target = typed.parse().unwrap_or(steps);
+                    typed.clear();
+                }
+                Ok(Key::Quit) => break,
+                Ok(Key::Other) => {}
+            }
+        }
+        write!(out, "{SHOW_CURSOR}")?;
+        out.flush()?;
+        Ok(steps)
+    }
+}
+
+/// Writes frames as images instead of playing them.
+#[derive(Debug, Clone)]
+pub struct FrameExport {
+    pub dir: PathBuf,
+    pub format: Format,
+    pub cell_size: usize,
+    /// Palette overrides applied on top of the palette of a day.
+    pub palette: Option<String>,
+    pub fps: f64,
+    /// Steps to skip before the first frame.
+    pub skip: usize,
+}
+
+impl FrameExport {
+    /// Reads `--frames <dir>`, `--format png|ppm|gif`, `--cell-size <pixels>`,
+    /// `--palette <overrides>`, `--fps <n>` and `--jump <step>` from the command line.
+    /// Returns `None` without `--frames`.
+    pub fn from_args() -> Option<Self> {
+        let args: Vec<String> = env::args().collect();
+        Some(Self {
+            dir: arg_value(&args, "--frames")?,
+            format: arg_value(&args, "--format").unwrap_or(Format::Png),
+            cell_size: arg_value(&args, "--cell-size").unwrap_or(4),
+            palette: arg_value(&args, "--palette"),
+            fps: arg_value(&args, "--fps").unwrap_or(10.0),
+            skip: arg_value(&args, "--jump").unwrap_or(0),
+        })
+    }
+
+    /// Draws a text render with the palette of a day and any overrides.
+    pub fn image(&self, text: &str, palette: &Palette) -> Image {
+        let palette = match &self.palette {
+            Some(overrides) => palette
+                .clone()
+                .with_overrides(overrides)
+                .unwrap_or_else(|err| {
+                    eprintln!("Ignoring palette: {err}");
+                    palette.clone()
+                }),
+            None => palette.clone(),
+        };
+        Image::from_text(text, self.cell_size, &palette)
+    }
+
+    /// Saves a single image as `<name>.<ext>` and returns its path.
+    pub fn save(&self, name: &str, image: &Image) -> io::Result<PathBuf> {
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join(format!("{name}.{}", self.format.extension()));
+        image.save(&path, self.format)?;
+        Ok(path)
+    }
+
+    /// Writes a frame for every step of the simulation, including its initial and final state.
+    /// Returns the number of frames written.
+    pub fn write<S: Simulation>(&self, simulation: &mut S, palette: &Palette) -> io::Result<usize> {
+        for _ in 0..self.skip {
+            if simulation.is_done() {
+                break;
+            }
+            simulation.step();
+        }
+
+        let delay = (100.0 / self.fps).round().max(2.0) as u16;
+        let mut frames = FrameWriter::new(&self.dir, self.format, delay)?;
+        loop {
+            frames.write(&self.image(&simulation.render(), palette))?;
+            if simulation.is_done() {
+                break;
+            }
+            simulation.step();
+        }
+        frames.finish()
+    }
+}
+
+/// The render hook of a day: plays the simulation in the terminal, or writes its frames when
+/// `--frames <dir>` is given.
+pub fn visualize<S: Simulation>(simulation: &mut S, palette: &Palette) {
+    match FrameExport::from_args() {
+        Some(export) => {
+            let frames = export
+                .write(simulation, palette)
+                .expect("Should be able to write frames");
+            println!("Wrote {frames} frames to {}", export.dir.display());
+        }
+        None => {
+            Player::from_args()
+                .play(simulation)
+                .expect("Should be able to write to the terminal");
+        }
+    }
+}
+
+/// Parses the value after `flag` in `args`, warning about values that do not parse.
+pub fn arg_value<T: FromStr>(args: &[String], flag: &str) -> Option<T> {
+    let value = args.get(args.iter().position(|x| x == flag)? + 1)?;
+    let parsed = value.parse().ok();
+    if parsed.is_none() {
+        eprintln!("Ignoring invalid value for {flag}: {value}");
+    }
+    parsed
+}
+
+fn draw(out: &mut impl Write, frame: &str, status: &str) -> io::Result<()> {
+    write!(out, "{CURSOR_HOME}")?;
+    for line in frame.lines() {
+        writeln!(out, "{line}{CLEAR_LINE}")?;
+    }
+    writeln!(
+        out,
+        "{ANSI_BOLD}{status}{ANSI_RESET}{CLEAR_LINE}{CLEAR_BELOW}"
+    )?;
+    out.flush()
+}
+
+/// Forwards every byte read from stdin. The thread ends with stdin.
+fn read_keys() -> Receiver<u8> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for byte in io::stdin().lock().bytes() {
+            let Ok(byte) = byte else { break };
+            if sender.send(byte).is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+/// Puts the terminal into raw mode via `stty` and restores its previous settings when dropped.
+struct RawMode {
+    saved: String,
+}
+
+impl RawMode {
+    fn enable() -> Option<Self> {
+        if !io::stdin().is_terminal() {
+            return None;
+        }
+        let saved = stty(&["-g"])?;
+        stty(&["-icanon", "-echo", "-isig", "min", "1"])?;
+        Some(Self { saved })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        stty(&[&self.saved]);
+    }
+}
+
+fn stty(args: &[&str]) -> Option<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(feature = "test_lib")]
+mod tests {
+    use super::{Player, Simulation};
+    use std::sync::mpsc;
+
+    struct Countdown(u32);
+
+    impl Simulation for Countdown {
+        fn step(&mut self) {
+            self.0 -= 1;
+        }
+
+        fn render(&self) -> String {
+            format!("<{}>", self.0)
+        }
+
+        fn is_done(&self) -> bool {
+            self.0 == 0
+        }
+    }
+
+    fn play(player: Player, keys: Option<&[u8]>) -> (usize, String) {
+        let keys = keys.map(|keys| {
+            let (sender, receiver) = mpsc::channel();
+            keys.iter().for_each(|&key| sender.send(key).unwrap());
+            receiver
+        });
+        let mut out = vec![];
+        let steps = player.run(&mut Countdown(10), &mut out, keys).unwrap();
+        (steps, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn plays_until_done() {
+        let (steps, out) = play(Player::new().fps(10_000.0), None);
+        assert_eq!(steps, 10);
+        assert!(out.contains("<10>"));
+        assert!(out.contains("<0>"));
+        assert!(out.contains("done after 10 steps"));
+    }
+
+    #[test]
+    fn jumps_ahead_before_first_frame() {
+        let (steps, out) = play(Player::new().fps(10_000.0).jump_to(7), None);
+        assert_eq!(steps, 10);
+        assert!(!out.contains("<8>"));
+        assert!(out.contains("<3>"));
+    }
+
+    #[test]
+    fn steps_and_jumps_with_keys() {
+        let player = Player::new().fps(10_000.0).paused(true);
+        let (steps, out) = play(player, Some(b"nn5gq"));
+        assert_eq!(steps, 5);
+        assert!(out.contains("step 2 | paused"));
+        assert!(out.contains("step 2 | paused | 5"));
+        assert!(out.contains("<5>"));
+        assert!(!out.contains("<4>"));
+    }
+}
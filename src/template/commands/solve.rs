@@ -2,7 +2,26 @@ use std::process::{Command, Stdio};
 
 use crate::template::Day;
 
-pub fn handle(day: Day, release: bool, dhat: bool, submit_part: Option<u8>) {
+/// Playback and frame export options for days that implement a `visualize` render hook.
+pub struct Visualize {
+    pub fps: Option<f64>,
+    pub jump: Option<usize>,
+    pub paused: bool,
+    pub frames: Option<String>,
+    pub format: Option<String>,
+    pub cell_size: Option<usize>,
+    pub palette: Option<String>,
+}
+
+pub fn handle(
+    day: Day,
+    release: bool,
+    dhat: bool,
+    submit_part: Option<u8>,
+    variant: Option<String>,
+    explain: bool,
+    visualize: Option<Visualize>,
+) {
     let mut cmd_args = vec!["run".to_string(), "--bin".to_string(), day.to_string()];
 
     if dhat {
@@ -23,6 +42,39 @@ pub fn handle(day: Day, release: bool, dhat: bool, submit_part: Option<u8>) {
         cmd_args.push(submit_part.to_string());
     }
 
+    if let Some(variant) = variant {
+        cmd_args.push("--variant".to_string());
+        cmd_args.push(variant);
+    }
+
+    if explain {
+        cmd_args.push("--explain".to_string());
+    }
+
+    if let Some(visualize) = visualize {
+        cmd_args.push("--visualize".to_string());
+        let options = [
+            ("--fps", visualize.fps.map(|fps| fps.to_string())),
+            ("--jump", visualize.jump.map(|jump| jump.to_string())),
+            ("--frames", visualize.frames),
+            ("--format", visualize.format),
+            (
+                "--cell-size",
+                visualize.cell_size.map(|size| size.to_string()),
+            ),
+            ("--palette", visualize.palette),
+        ];
+        for (flag, value) in options {
+            if let Some(value) = value {
+                cmd_args.push(flag.to_string());
+                cmd_args.push(value);
+            }
+        }
+        if visualize.paused {
+            cmd_args.push("--paused".to_string());
+        }
+    }
+
     let mut cmd = Command::new("cargo")
         .args(&cmd_args)
         .stdout(Stdio::inherit())
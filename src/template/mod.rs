@@ -3,6 +3,7 @@ use std::{env, fs};
 pub mod aoc_cli;
 pub mod commands;
 pub mod runner;
+pub mod variants;
 
 pub use day::*;
 
@@ -14,6 +15,10 @@ mod timings;
 pub const ANSI_ITALIC: &str = "\x1b[3m";
 pub const ANSI_BOLD: &str = "\x1b[1m";
 pub const ANSI_RESET: &str = "\x1b[0m";
+pub const ANSI_RED: &str = "\x1b[31m";
+pub const ANSI_GREEN: &str = "\x1b[32m";
+pub const ANSI_YELLOW: &str = "\x1b[33m";
+pub const ANSI_BLUE: &str = "\x1b[34m";
 
 /// Helper function that reads a text file to a string.
 #[must_use]
@@ -36,9 +41,38 @@ pub fn read_file_part(folder: &str, day: Day, part: u8) -> String {
     f.expect("could not open input file")
 }
 
+/// Helper function that reads every example file of a day, i.e. `01.txt` as well as any part files like `01-2.txt`.
+#[must_use]
+pub fn read_examples(day: Day) -> Vec<String> {
+    let cwd = env::current_dir().unwrap();
+    let folder = cwd.join("data").join("examples");
+    let part_prefix = format!("{day}-");
+    let mut paths: Vec<_> = fs::read_dir(folder)
+        .expect("could not open examples folder")
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let stem = path.file_stem()?.to_str()?;
+            let is_example = path.extension()? == "txt"
+                && (stem == day.to_string() || stem.starts_with(&part_prefix));
+            is_example.then_some(path)
+        })
+        .collect();
+    paths.sort();
+    paths
+        .into_iter()
+        .map(|path| fs::read_to_string(path).expect("could not open example file"))
+        .collect()
+}
+
 /// Creates the constant `DAY` and sets up the input and runner for each part.
 ///
 /// The optional, second parameter (1 or 2) allows you to only run a single part of the solution.
+/// A `visualize` function taking the input runs instead of the parts when `--visualize` or
+/// `--frames <dir>` is passed.
+///
+/// ```ignore
+/// advent_of_code::solution!(6; visualize = visualize);
+/// ```
 #[macro_export]
 macro_rules! solution {
     ($day:expr) => {
@@ -50,8 +84,11 @@ macro_rules! solution {
     ($day:expr, 2) => {
         $crate::solution!(@impl $day, [part_two, 2]);
     };
+    ($day:expr; $( $hook:ident = $hook_func:expr ),+ $(,)?) => {
+        $crate::solution!(@impl $day, [part_one, 1] [part_two, 2]; $( $hook = $hook_func ),+);
+    };
 
-    (@impl $day:expr, $( [$func:expr, $part:expr] )*) => {
+    (@impl $day:expr, $( [$func:expr, $part:expr] )* $(; $( $hook:ident = $hook_func:expr ),+)?) => {
         /// The current day.
         const DAY: $crate::template::Day = $crate::day!($day);
 
@@ -62,7 +99,105 @@ macro_rules! solution {
         fn main() {
             use $crate::template::runner::*;
             let input = $crate::template::read_file("inputs", DAY);
+            $($(
+                if $crate::solution!(@hook $hook, $hook_func, &input) {
+                    return;
+                }
+            )+)?
             $( run_part($func, &input, DAY, $part); )*
         }
     };
+
+    // `visualize` replaces solving, `explain` prints before solving.
+    (@hook visualize, $func:expr, $input:expr) => {
+        visualize_requested() && {
+            $func($input);
+            true
+        }
+    };
+    (@hook explain, $func:expr, $input:expr) => {{
+        if explain_requested() {
+            $func($input);
+        }
+        false
+    }};
+}
+
+/// Like `solution!`, but registers several named implementations per part.
+///
+/// The first variant listed for a part is its baseline: it runs unless `--variant <name>` selects
+/// another one, and every other variant is checked against it by a generated test. The test runs
+/// all example files of the day and, if a generator is given, a batch of generated inputs.
+/// Hooks such as `explain = explain` can follow the generator, as with `solution!`.
+///
+/// ```ignore
+/// advent_of_code::variants!(2,
+///     1 => [naive: part_one],
+///     2 => [naive: part_two, fast: part_two_fast];
+///     generate = tests::random_input,
+/// );
+/// ```
+#[macro_export]
+macro_rules! variants {
+    ($day:expr, $( $part:literal => [ $( $name:ident : $func:expr ),+ $(,)? ] ),+ $(,)?) => {
+        $crate::variants!(@impl $day, [ $( $part => [ $( $name : $func ),+ ] )+ ] [] []);
+    };
+    ($day:expr, $( $part:literal => [ $( $name:ident : $func:expr ),+ $(,)? ] ),+ ; generate = $gen:expr $(, $hook:ident = $hook_func:expr )* $(,)?) => {
+        $crate::variants!(@impl $day, [ $( $part => [ $( $name : $func ),+ ] )+ ] [$gen] [ $( $hook = $hook_func ),* ]);
+    };
+    ($day:expr, $( $part:literal => [ $( $name:ident : $func:expr ),+ $(,)? ] ),+ ; $( $hook:ident = $hook_func:expr ),+ $(,)?) => {
+        $crate::variants!(@impl $day, [ $( $part => [ $( $name : $func ),+ ] )+ ] [] [ $( $hook = $hook_func ),+ ]);
+    };
+
+    (@impl $day:expr, [ $( $part:literal => [ $( $name:ident : $func:expr ),+ ] )+ ] [ $( $gen:expr )? ] [ $( $hook:ident = $hook_func:expr ),* ]) => {
+        /// The current day.
+        const DAY: $crate::template::Day = $crate::day!($day);
+
+        #[cfg(feature = "dhat-heap")]
+        #[global_allocator]
+        static ALLOC: dhat::Alloc = dhat::Alloc;
+
+        fn main() {
+            use $crate::template::runner::*;
+            use $crate::template::variants::Variant;
+            let input = $crate::template::read_file("inputs", DAY);
+            $(
+                if $crate::solution!(@hook $hook, $hook_func, &input) {
+                    return;
+                }
+            )*
+            $(
+                let variants: &[Variant<&str, _>] = &[ $( (stringify!($name), $func) ),+ ];
+                run_part_variant(variants, &input, DAY, $part);
+            )+
+        }
+
+        #[cfg(test)]
+        mod variant_tests {
+            use super::*;
+            use $crate::template::variants::{assert_agree, Variant};
+
+            fn inputs() -> Vec<String> {
+                #[allow(unused_mut)]
+                let mut inputs = $crate::template::read_examples(DAY);
+                $(
+                    inputs.extend((0..$crate::template::variants::GENERATED_INPUTS).map(|seed| {
+                        $gen(&mut $crate::template::variants::Rng::new(seed))
+                    }));
+                )?
+                inputs
+            }
+
+            #[test]
+            fn variants_agree() {
+                let inputs = inputs();
+                $(
+                    let variants: &[Variant<&str, _>] = &[ $( (stringify!($name), $func) ),+ ];
+                    for input in &inputs {
+                        assert_agree(variants, input.as_str(), $part);
+                    }
+                )+
+            }
+        }
+    };
 }
@@ -52,8 +52,42 @@ fn locate_table(readme: &str) -> Result<TablePosition, Error> {
     Ok(TablePosition { pos_start, pos_end })
 }
 
+/// Rows comparing the fastest variant of each part that has several variants to its baseline.
+fn construct_variant_rows(timings: &Timings) -> Vec<String> {
+    let mut rows = vec![];
+
+    for timing in &timings.data {
+        for part in [1, 2] {
+            let variants = timing.part_variants(part);
+            if variants.len() < 2 {
+                continue;
+            }
+            let baseline = variants[0];
+            let fastest = variants
+                .iter()
+                .min_by(|a, b| a.nanos.total_cmp(&b.nanos))
+                .expect("Part has variants");
+
+            rows.push(format!(
+                "| [Day {}]({}) | {} | `{}` `{}` | `{}` `{}` | {:.2}x |",
+                timing.day.into_inner(),
+                get_path_for_bin(timing.day),
+                part,
+                fastest.variant,
+                fastest.timing,
+                baseline.variant,
+                baseline.timing,
+                baseline.nanos / fastest.nanos
+            ));
+        }
+    }
+
+    rows
+}
+
 fn construct_table(prefix: &str, timings: Timings, total_millis: f64) -> String {
     let header = format!("{prefix} Benchmarks");
+    let variant_rows = construct_variant_rows(&timings);
 
     let mut lines: Vec<String> = vec![
         MARKER.into(),
@@ -76,6 +110,16 @@ fn construct_table(prefix: &str, timings: Timings, total_millis: f64) -> String
 
     lines.push(String::new());
     lines.push(format!("**Total: {total_millis:.2}ms**"));
+
+    if !variant_rows.is_empty() {
+        lines.push(String::new());
+        lines.push(format!("{prefix}# Variants"));
+        lines.push(String::new());
+        lines.push("| Day | Part | Fastest | Baseline | Speedup |".into());
+        lines.push("| :---: | :---: | :---: | :---: | :---: |".into());
+        lines.extend(variant_rows);
+    }
+
     lines.push(MARKER.into());
 
     lines.join("\n")
@@ -100,7 +144,10 @@ pub fn update(timings: Timings) -> Result<(), Error> {
 #[cfg(feature = "test_lib")]
 mod tests {
     use super::{update_content, MARKER};
-    use crate::{day, template::timings::Timing, template::timings::Timings};
+    use crate::{
+        day, template::timings::Timing, template::timings::Timings,
+        template::timings::VariantTiming,
+    };
 
     fn get_mock_timings() -> Timings {
         Timings {
@@ -110,18 +157,21 @@ mod tests {
                     part_1: Some("10ms".into()),
                     part_2: Some("20ms".into()),
                     total_nanos: 3e+10,
+                    variants: vec![],
                 },
                 Timing {
                     day: day!(2),
                     part_1: Some("30ms".into()),
                     part_2: Some("40ms".into()),
                     total_nanos: 7e+10,
+                    variants: vec![],
                 },
                 Timing {
                     day: day!(4),
                     part_1: Some("40ms".into()),
                     part_2: Some("50ms".into()),
                     total_nanos: 9e+10,
+                    variants: vec![],
                 },
             ],
         }
@@ -180,4 +230,43 @@ mod tests {
         .join("\n");
         assert_eq!(s, expected);
     }
+
+    #[test]
+    fn format_variant_benchmarks() {
+        let mut timings = get_mock_timings();
+        timings.data[1].variants = vec![
+            VariantTiming {
+                part: 1,
+                variant: "naive".into(),
+                timing: "30ms".into(),
+                nanos: 3e+7,
+            },
+            VariantTiming {
+                part: 2,
+                variant: "naive".into(),
+                timing: "40ms".into(),
+                nanos: 4e+7,
+            },
+            VariantTiming {
+                part: 2,
+                variant: "fast".into(),
+                timing: "10ms".into(),
+                nanos: 1e+7,
+            },
+        ];
+        let mut s = format!("{}\n{}", MARKER, MARKER);
+        update_content(&mut s, timings, 190.0).unwrap();
+        let expected = [
+            "**Total: 190.00ms**",
+            "",
+            "### Variants",
+            "",
+            "| Day | Part | Fastest | Baseline | Speedup |",
+            "| :---: | :---: | :---: | :---: | :---: |",
+            "| [Day 2](./src/bin/02.rs) | 2 | `fast` `10ms` | `naive` `40ms` | 4.00x |",
+            "<!--- benchmarking table --->",
+        ]
+        .join("\n");
+        assert_eq!(s.ends_with(&expected), true);
+    }
 }
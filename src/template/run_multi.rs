@@ -4,10 +4,15 @@ use crate::template::{Day, ANSI_BOLD, ANSI_ITALIC, ANSI_RESET};
 
 use super::{
     all_days,
-    timings::{Timing, Timings},
+    timings::{Timing, Timings, VariantTiming},
 };
 
-pub fn run_multi(days_to_run: &HashSet<Day>, is_release: bool, is_timed: bool) -> Option<Timings> {
+pub fn run_multi(
+    days_to_run: &HashSet<Day>,
+    is_release: bool,
+    is_timed: bool,
+    all_variants: bool,
+) -> Option<Timings> {
     let mut timings: Vec<Timing> = Vec::with_capacity(days_to_run.len());
 
     let mut need_space = false;
@@ -24,7 +29,8 @@ pub fn run_multi(days_to_run: &HashSet<Day>, is_release: bool, is_timed: bool) -
             println!("{ANSI_BOLD}Day {day}{ANSI_RESET}");
             println!("------");
 
-            let output = child_commands::run_solution(day, is_timed, is_release).unwrap();
+            let output =
+                child_commands::run_solution(day, is_timed, is_release, all_variants).unwrap();
 
             if output.is_empty() {
                 println!("Not solved.");
@@ -77,7 +83,12 @@ pub mod child_commands {
     };
 
     /// Run the solution bin for a given day
-    pub fn run_solution(day: Day, is_timed: bool, is_release: bool) -> Result<Vec<String>, Error> {
+    pub fn run_solution(
+        day: Day,
+        is_timed: bool,
+        is_release: bool,
+        all_variants: bool,
+    ) -> Result<Vec<String>, Error> {
         // skip command invocation for days that have not been scaffolded yet.
         if !Path::new(&get_path_for_bin(day)).exists() {
             return Ok(vec![]);
@@ -90,12 +101,18 @@ pub mod child_commands {
             args.push("--release");
         }
 
+        args.push("--");
+
         if is_timed {
             // mirror `--time` flag to child invocations.
-            args.push("--");
             args.push("--time");
         }
 
+        if all_variants {
+            // run every registered implementation of a part instead of its baseline.
+            args.push("--variants");
+        }
+
         // spawn child command with piped stdout/stderr.
         // forward output to stdout/stderr while grabbing stdout lines.
 
@@ -134,6 +151,7 @@ pub mod child_commands {
             part_1: None,
             part_2: None,
             total_nanos: 0_f64,
+            variants: vec![],
         };
 
         output
@@ -152,18 +170,41 @@ pub mod child_commands {
                 Some((part, timing_str, nanos))
             })
             .for_each(|(part, timing_str, nanos)| {
-                if part.contains("Part 1") {
-                    timings.part_1 = Some(timing_str.into());
+                let (part_number, part_timing) = if part.contains("Part 1") {
+                    (1, &mut timings.part_1)
                 } else if part.contains("Part 2") {
-                    timings.part_2 = Some(timing_str.into());
+                    (2, &mut timings.part_2)
+                } else {
+                    timings.total_nanos += nanos;
+                    return;
+                };
+
+                if let Some(variant) = parse_variant(part) {
+                    timings.variants.push(super::VariantTiming {
+                        part: part_number,
+                        variant: variant.into(),
+                        timing: timing_str.into(),
+                        nanos,
+                    });
                 }
 
-                timings.total_nanos += nanos;
+                // the first timing of a part belongs to its baseline; only count that one.
+                if part_timing.is_none() {
+                    *part_timing = Some(timing_str.into());
+                    timings.total_nanos += nanos;
+                }
             });
 
         timings
     }
 
+    /// Parse the variant name from a part label such as `Part 2 (fast)`.
+    fn parse_variant(part: &str) -> Option<&str> {
+        let (_, rest) = part.split_once('(')?;
+        let (variant, _) = rest.split_once(')')?;
+        Some(variant.trim())
+    }
+
     fn parse_to_float(s: &str, postfix: &str) -> Option<f64> {
         s.split(postfix).next()?.parse().ok()
     }
@@ -174,7 +215,7 @@ pub mod child_commands {
             .split(" samples)")
             .next()?
             .split('(')
-            .last()?
+            .next_back()?
             .split('@')
             .next()?
             .trim();
@@ -239,6 +280,27 @@ pub mod child_commands {
             assert_eq!(res.part_2.unwrap(), "100ms");
         }
 
+        #[test]
+        fn parses_variant_timings() {
+            let res = parse_exec_time(
+                &[
+                    "Part 1: 2 (10ms @ 100 samples)".into(),
+                    "Part 2 (naive): 4 (40ms @ 25 samples)".into(),
+                    "Part 2 (fast): 4 (10ms @ 100 samples)".into(),
+                    "".into(),
+                ],
+                day!(2),
+            );
+            assert_approx_eq!(res.total_nanos, 50000000_f64);
+            assert_eq!(res.part_1.unwrap(), "10ms");
+            assert_eq!(res.part_2.unwrap(), "40ms");
+            assert_eq!(res.variants.len(), 2);
+            assert_eq!(res.variants[0].part, 2);
+            assert_eq!(res.variants[0].variant, "naive");
+            assert_eq!(res.variants[1].variant, "fast");
+            assert_eq!(res.variants[1].timing, "10ms");
+        }
+
         #[test]
         fn parses_missing_parts() {
             let res = parse_exec_time(
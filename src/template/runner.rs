@@ -3,15 +3,110 @@ use std::fmt::Display;
 use std::hint::black_box;
 use std::io::{stdout, Write};
 use std::process::Output;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::thread::{self, ThreadId};
 use std::time::{Duration, Instant};
 use std::{cmp, env, process};
 
+use crate::template::variants::Variant;
 use crate::template::ANSI_BOLD;
 use crate::template::{aoc_cli, Day, ANSI_ITALIC, ANSI_RESET};
 
+/// The number of runs started so far. A new run starts before every benchmark sample.
+static RUN: AtomicUsize = AtomicUsize::new(0);
+
+/// State shared by the parts of a day within one run, e.g. a cache that part one warms for
+/// part two.
+///
+/// It starts over before every benchmark sample, so `cargo time` measures each part from a cold
+/// state, and when used from a different thread than last time, so tests running in parallel do
+/// not see each other's state.
+pub struct RunState<T> {
+    state: Mutex<Option<(usize, ThreadId, T)>>,
+}
+
+impl<T: Default> RunState<T> {
+    pub const fn new() -> Self {
+        Self {
+            state: Mutex::new(None),
+        }
+    }
+
+    /// Calls `f` with the state of the current run.
+    pub fn with<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        let run = RUN.load(Ordering::Relaxed);
+        let thread = thread::current().id();
+        // a panic while the state was in use may have left it half updated.
+        let poisoned = self.state.is_poisoned();
+        self.state.clear_poison();
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let current = matches!(&*state, Some((r, t, _)) if *r == run && *t == thread);
+        if poisoned || !current {
+            *state = Some((run, thread, T::default()));
+        }
+        let (_, _, state) = state.as_mut().expect("State was just set");
+        f(state)
+    }
+}
+
+impl<T: Default> Default for RunState<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 pub fn run_part<I: Clone, T: Display>(func: impl Fn(I) -> Option<T>, input: I, day: Day, part: u8) {
-    let part_str = format!("Part {part}");
+    run_labelled_part(func, input, day, part, format!("Part {part}"));
+}
+
+/// Run the variant of a part selected with `--variant <name>`, or its baseline (first) variant.
+/// Parts that do not register the selected variant are skipped.
+/// With `--variants`, every variant of the part is run in order, starting with the baseline.
+pub fn run_part_variant<I: Clone, T: Display>(
+    variants: &[Variant<I, T>],
+    input: I,
+    day: Day,
+    part: u8,
+) {
+    if env::args().any(|x| x == "--variants") {
+        for (name, func) in variants {
+            let input = input.clone();
+            run_labelled_part(func, input, day, part, format!("Part {part} ({name})"));
+        }
+        return;
+    }
+
+    let selected = match selected_variant() {
+        None => variants.first(),
+        Some(name) => variants.iter().find(|(variant, _)| *variant == name),
+    };
 
+    let Some((name, func)) = selected else {
+        let available = variants.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+        println!(
+            "Part {part}: ✖ (available variants: {})",
+            available.join(", ")
+        );
+        return;
+    };
+
+    let part_str = if variants.len() > 1 {
+        format!("Part {part} ({name})")
+    } else {
+        format!("Part {part}")
+    };
+
+    run_labelled_part(func, input, day, part, part_str);
+}
+
+fn run_labelled_part<I: Clone, T: Display>(
+    func: impl Fn(I) -> Option<T>,
+    input: I,
+    day: Day,
+    part: u8,
+    part_str: String,
+) {
     let (result, duration, samples) =
         run_timed(func, input, |result| print_result(result, &part_str, ""));
 
@@ -66,6 +161,7 @@ fn bench<I: Clone, T>(func: impl Fn(I) -> T, input: I, base_time: &Duration) ->
     for _ in 0..bench_iterations {
         // need a clone here to make the borrow checker happy.
         let cloned = input.clone();
+        RUN.fetch_add(1, Ordering::Relaxed);
         let timer = Instant::now();
         black_box(func(black_box(cloned)));
         timers.push(timer.elapsed());
@@ -129,6 +225,29 @@ fn print_result<T: Display>(result: &Option<T>, part: &str, duration_str: &str)
     }
 }
 
+/// Whether `solve` was called with `--visualize` or `--frames <dir>`.
+pub fn visualize_requested() -> bool {
+    env::args().any(|x| x == "--visualize" || x == "--frames")
+}
+
+/// Whether `solve` was called with `--explain`.
+pub fn explain_requested() -> bool {
+    env::args().any(|x| x == "--explain")
+}
+
+/// Parse the `--variant <name>` argument passed to `solve`.
+fn selected_variant() -> Option<String> {
+    let args: Vec<String> = env::args().collect();
+    let variant_index = args.iter().position(|x| x == "--variant")? + 1;
+
+    let Some(variant) = args.get(variant_index) else {
+        eprintln!("Unexpected command-line input. Format: cargo solve 1 --variant fast");
+        process::exit(1);
+    };
+
+    Some(variant.clone())
+}
+
 /// Parse the arguments passed to `solve` and try to submit one part of the solution if:
 ///  1. we are in `--release` mode.
 ///  2. aoc-cli is installed.
@@ -167,3 +286,26 @@ fn submit_result<T: Display>(
     println!("Submitting result via aoc-cli...");
     Some(aoc_cli::submit(day, part, &result.to_string()))
 }
+
+#[cfg(feature = "test_lib")]
+mod tests {
+    use super::{Ordering, RunState, RUN};
+    use std::thread;
+
+    #[test]
+    fn shares_state_within_a_run() {
+        let state: RunState<Vec<u8>> = RunState::new();
+        state.with(|values| values.push(1));
+        assert_eq!(state.with(|values| values.clone()), [1]);
+
+        // other threads, e.g. other tests, start from scratch
+        thread::scope(|scope| {
+            scope.spawn(|| assert!(state.with(|values| values.is_empty())));
+        });
+        assert!(state.with(|values| values.is_empty()));
+
+        state.with(|values| values.push(2));
+        RUN.fetch_add(1, Ordering::Relaxed);
+        assert!(state.with(|values| values.is_empty()));
+    }
+}
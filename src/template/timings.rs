@@ -6,12 +6,23 @@ use crate::template::Day;
 static TIMINGS_FILE_PATH: &str = "./data/timings.json";
 
 /// Represents benchmark times for a single day.
+/// `part_1` and `part_2` hold the timings of the baseline implementations.
 #[derive(Clone, Debug)]
 pub struct Timing {
     pub day: Day,
     pub part_1: Option<String>,
     pub part_2: Option<String>,
     pub total_nanos: f64,
+    pub variants: Vec<VariantTiming>,
+}
+
+/// Represents the benchmark time of one named implementation of a part, as registered with `variants!`.
+#[derive(Clone, Debug)]
+pub struct VariantTiming {
+    pub part: u8,
+    pub variant: String,
+    pub timing: String,
+    pub nanos: f64,
 }
 
 /// Represents benchmark times for a set of days.
@@ -37,12 +48,16 @@ impl Timings {
             .unwrap_or_default()
     }
 
-    /// Merge two sets of timings, overwriting `self` with `other` if present.
+    /// Merge two sets of timings, overwriting days of `self` with those of `new` if present.
+    /// Stored variant timings that `new` did not measure are kept.
     pub fn merge(&self, new: &Self) -> Self {
         let mut data: Vec<Timing> = vec![];
 
         for timing in &new.data {
-            data.push(timing.clone());
+            match self.data.iter().find(|t| t.day == timing.day) {
+                Some(stored) => data.push(stored.merge(timing)),
+                None => data.push(timing.clone()),
+            }
         }
 
         for timing in &self.data {
@@ -51,7 +66,7 @@ impl Timings {
             }
         }
 
-        data.sort_unstable_by(|a, b| a.day.cmp(&b.day));
+        data.sort_unstable_by_key(|a| a.day);
         Timings { data }
     }
 
@@ -67,6 +82,42 @@ impl Timings {
     }
 }
 
+impl Timing {
+    /// Takes the part timings of `new` along with its variant timings, keeping stored variants
+    /// it did not measure in their original order.
+    fn merge(&self, new: &Self) -> Self {
+        let is_same =
+            |a: &VariantTiming, b: &VariantTiming| a.part == b.part && a.variant == b.variant;
+        let mut variants: Vec<VariantTiming> = self
+            .variants
+            .iter()
+            .map(|stored| {
+                new.variants
+                    .iter()
+                    .find(|v| is_same(v, stored))
+                    .unwrap_or(stored)
+                    .clone()
+            })
+            .collect();
+        for variant in &new.variants {
+            if !variants.iter().any(|v| is_same(v, variant)) {
+                variants.push(variant.clone());
+            }
+        }
+        variants.sort_by_key(|v| v.part);
+
+        Timing {
+            variants,
+            ..new.clone()
+        }
+    }
+
+    /// Variant timings of a part, baseline first.
+    pub fn part_variants(&self, part: u8) -> Vec<&VariantTiming> {
+        self.variants.iter().filter(|v| v.part == part).collect()
+    }
+}
+
 /* -------------------------------------------------------------------------- */
 
 impl From<Timings> for JsonValue {
@@ -133,6 +184,11 @@ impl From<&Timing> for JsonValue {
             },
         );
 
+        map.insert(
+            "variants".into(),
+            JsonValue::Array(value.variants.iter().map(JsonValue::from).collect()),
+        );
+
         JsonValue::Object(map)
     }
 }
@@ -166,11 +222,78 @@ impl TryFrom<&JsonValue> for Timing {
             .and_then(|v| v.get::<f64>().copied())
             .ok_or("Expected timing.total_nanos to be a number.")?;
 
+        // timings stored before variants were tracked do not have this key.
+        let variants = match json.get("variants") {
+            None => vec![],
+            Some(v) => v
+                .get::<Vec<JsonValue>>()
+                .ok_or("Expected timing.variants to be an array.")?
+                .iter()
+                .map(VariantTiming::try_from)
+                .collect::<Result<_, _>>()?,
+        };
+
         Ok(Timing {
             day,
             part_1: part_1.cloned(),
             part_2: part_2.cloned(),
             total_nanos,
+            variants,
+        })
+    }
+}
+
+/* -------------------------------------------------------------------------- */
+
+impl From<&VariantTiming> for JsonValue {
+    fn from(value: &VariantTiming) -> Self {
+        let mut map: HashMap<String, JsonValue> = HashMap::new();
+
+        map.insert("part".into(), JsonValue::Number(f64::from(value.part)));
+        map.insert("variant".into(), JsonValue::String(value.variant.clone()));
+        map.insert("timing".into(), JsonValue::String(value.timing.clone()));
+        map.insert("nanos".into(), JsonValue::Number(value.nanos));
+
+        JsonValue::Object(map)
+    }
+}
+
+impl TryFrom<&JsonValue> for VariantTiming {
+    type Error = String;
+
+    fn try_from(value: &JsonValue) -> Result<Self, Self::Error> {
+        let json = value
+            .get::<HashMap<String, JsonValue>>()
+            .ok_or("Expected variant timing to be a JSON object.")?;
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let part = json
+            .get("part")
+            .and_then(|v| v.get::<f64>())
+            .filter(|part| **part == 1.0 || **part == 2.0)
+            .map(|part| *part as u8)
+            .ok_or("Expected variant.part to be 1 or 2.")?;
+
+        let variant = json
+            .get("variant")
+            .and_then(|v| v.get::<String>())
+            .ok_or("Expected variant.variant to be a string.")?;
+
+        let timing = json
+            .get("timing")
+            .and_then(|v| v.get::<String>())
+            .ok_or("Expected variant.timing to be a string.")?;
+
+        let nanos = json
+            .get("nanos")
+            .and_then(|v| v.get::<f64>().copied())
+            .ok_or("Expected variant.nanos to be a number.")?;
+
+        Ok(VariantTiming {
+            part,
+            variant: variant.clone(),
+            timing: timing.clone(),
+            nanos,
         })
     }
 }
@@ -191,18 +314,21 @@ mod tests {
                     part_1: Some("10ms".into()),
                     part_2: Some("20ms".into()),
                     total_nanos: 3e+10,
+                    variants: vec![],
                 },
                 Timing {
                     day: day!(2),
                     part_1: Some("30ms".into()),
                     part_2: Some("40ms".into()),
                     total_nanos: 7e+10,
+                    variants: vec![],
                 },
                 Timing {
                     day: day!(4),
                     part_1: Some("40ms".into()),
                     part_2: None,
                     total_nanos: 4e+10,
+                    variants: vec![],
                 },
             ],
         }
@@ -223,6 +349,20 @@ mod tests {
             assert_eq!(timing.total_nanos, 1_000_000_000_f64);
         }
 
+        #[test]
+        fn handles_variant_timings() {
+            let json = r#"{ "data": [{ "day": "02", "part_1": "1ms", "part_2": "4ms", "total_nanos": 5000000, "variants": [{ "part": 2, "variant": "naive", "timing": "4ms", "nanos": 4000000 }, { "part": 2, "variant": "fast", "timing": "1ms", "nanos": 1000000 }] }] }"#.to_string();
+            let timings = Timings::try_from(json).unwrap();
+            let timing = timings.data.first().unwrap();
+            assert_eq!(timing.variants.len(), 2);
+            assert_eq!(timing.part_variants(1).len(), 0);
+            let part_2 = timing.part_variants(2);
+            assert_eq!(part_2[0].variant, "naive");
+            assert_eq!(part_2[1].variant, "fast");
+            assert_eq!(part_2[1].timing, "1ms");
+            assert_eq!(part_2[1].nanos, 1_000_000_f64);
+        }
+
         #[test]
         fn handles_empty_timings() {
             let json = r#"{ "data": [] }"#.to_string();
@@ -247,9 +387,27 @@ mod tests {
 
     mod serialization {
         use super::get_mock_timings;
+        use crate::template::timings::{Timings, VariantTiming};
         use std::collections::HashMap;
         use tinyjson::JsonValue;
 
+        #[test]
+        fn round_trips_variant_timings() {
+            let mut timings = get_mock_timings();
+            timings.data[0].variants = vec![VariantTiming {
+                part: 1,
+                variant: "fast".into(),
+                timing: "5ms".into(),
+                nanos: 5e+6,
+            }];
+            let json = JsonValue::from(timings).stringify().unwrap();
+            let timings = Timings::try_from(json).unwrap();
+            let variants = timings.data[0].part_variants(1);
+            assert_eq!(variants.len(), 1);
+            assert_eq!(variants[0].variant, "fast");
+            assert_eq!(variants[0].nanos, 5e+6);
+        }
+
         #[test]
         fn serializes_timings() {
             let timings = get_mock_timings();
@@ -282,10 +440,11 @@ mod tests {
                     part_1: Some("1ms".into()),
                     part_2: Some("2ms".into()),
                     total_nanos: 3_000_000_000_f64,
+                    variants: vec![],
                 }],
             };
 
-            assert_eq!(timings.is_day_complete(&day!(1)), true);
+            assert_eq!(timings.is_day_complete(day!(1)), true);
         }
 
         #[test]
@@ -296,10 +455,11 @@ mod tests {
                     part_1: Some("1ms".into()),
                     part_2: None,
                     total_nanos: 1_000_000_000_f64,
+                    variants: vec![],
                 }],
             };
 
-            assert_eq!(timings.is_day_complete(&day!(1)), false);
+            assert_eq!(timings.is_day_complete(day!(1)), false);
         }
 
         #[test]
@@ -310,17 +470,18 @@ mod tests {
                     part_1: None,
                     part_2: None,
                     total_nanos: 0.0,
+                    variants: vec![],
                 }],
             };
 
-            assert_eq!(timings.is_day_complete(&day!(1)), false);
+            assert_eq!(timings.is_day_complete(day!(1)), false);
         }
     }
 
     mod merge {
         use crate::{
             day,
-            template::timings::{Timing, Timings},
+            template::timings::{Timing, Timings, VariantTiming},
         };
 
         use super::get_mock_timings;
@@ -334,6 +495,7 @@ mod tests {
                     part_1: None,
                     part_2: None,
                     total_nanos: 0_f64,
+                    variants: vec![],
                 }],
             };
             let merged = timings.merge(&other);
@@ -354,6 +516,7 @@ mod tests {
                     part_1: None,
                     part_2: None,
                     total_nanos: 0_f64,
+                    variants: vec![],
                 }],
             };
             let merged = timings.merge(&other);
@@ -365,6 +528,66 @@ mod tests {
             assert_eq!(merged.data[2].day, day!(4));
         }
 
+        #[test]
+        fn keeps_unmeasured_variant_timings() {
+            let variant = |part, variant: &str, timing: &str| VariantTiming {
+                part,
+                variant: variant.into(),
+                timing: timing.into(),
+                nanos: 0_f64,
+            };
+            let timings = Timings {
+                data: vec![Timing {
+                    day: day!(2),
+                    part_1: Some("1ms".into()),
+                    part_2: Some("4ms".into()),
+                    total_nanos: 5e+6,
+                    variants: vec![variant(2, "naive", "4ms"), variant(2, "fast", "1ms")],
+                }],
+            };
+
+            let without_variants = Timings {
+                data: vec![Timing {
+                    day: day!(2),
+                    part_1: Some("2ms".into()),
+                    part_2: Some("3ms".into()),
+                    total_nanos: 5e+6,
+                    variants: vec![],
+                }],
+            };
+            let merged = timings.merge(&without_variants);
+            assert_eq!(merged.data.len(), 1);
+            assert_eq!(merged.data[0].part_1, Some("2ms".into()));
+            assert_eq!(merged.data[0].part_2, Some("3ms".into()));
+            let kept: Vec<_> = merged.data[0]
+                .variants
+                .iter()
+                .map(|v| (v.variant.as_str(), v.timing.as_str()))
+                .collect();
+            assert_eq!(kept, [("naive", "4ms"), ("fast", "1ms")]);
+
+            let one_variant = Timings {
+                data: vec![Timing {
+                    variants: vec![variant(1, "naive", "2ms"), variant(2, "fast", "500µs")],
+                    ..without_variants.data[0].clone()
+                }],
+            };
+            let merged = timings.merge(&one_variant);
+            let updated: Vec<_> = merged.data[0]
+                .variants
+                .iter()
+                .map(|v| (v.part, v.variant.as_str(), v.timing.as_str()))
+                .collect();
+            assert_eq!(
+                updated,
+                [
+                    (1, "naive", "2ms"),
+                    (2, "naive", "4ms"),
+                    (2, "fast", "500µs")
+                ]
+            );
+        }
+
         #[test]
         fn handles_empty_timings() {
             let timings = Timings::default();
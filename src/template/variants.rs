@@ -0,0 +1,95 @@
+/// Helpers for days that register several implementations of a part via `variants!`.
+use std::fmt::Debug;
+use std::ops::Range;
+
+/// Number of generated inputs each set of variants is checked against.
+pub const GENERATED_INPUTS: u64 = 200;
+
+/// A named implementation of a solution part.
+pub type Variant<I, T> = (&'static str, fn(I) -> Option<T>);
+
+/// Minimal deterministic PRNG (SplitMix64) used to generate inputs for differential tests.
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a value in the half-open `range`.
+    pub fn range(&mut self, range: Range<u64>) -> u64 {
+        assert!(!range.is_empty(), "cannot sample from an empty range");
+        range.start + self.next_u64() % (range.end - range.start)
+    }
+
+    pub fn bool(&mut self) -> bool {
+        self.next_u64() & 1 == 1
+    }
+}
+
+/// Asserts that every variant of a part produces the same result as the baseline (first) variant.
+pub fn assert_agree<I: Clone + Debug, T: PartialEq + Debug>(
+    variants: &[Variant<I, T>],
+    input: I,
+    part: u8,
+) {
+    let Some(((baseline_name, baseline), others)) = variants.split_first() else {
+        return;
+    };
+    let expected = baseline(input.clone());
+    for (name, func) in others {
+        let actual = func(input.clone());
+        assert_eq!(
+            expected, actual,
+            "Part {part}: variant `{name}` disagrees with `{baseline_name}` on input {input:?}"
+        );
+    }
+}
+
+#[cfg(feature = "test_lib")]
+mod tests {
+    use super::{assert_agree, Rng, Variant};
+
+    #[test]
+    fn rng_is_deterministic() {
+        let mut a = Rng::new(7);
+        let mut b = Rng::new(7);
+        for _ in 0..10 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+    }
+
+    #[test]
+    fn rng_range_is_bounded() {
+        let mut rng = Rng::new(1);
+        for _ in 0..1000 {
+            assert!((3..9).contains(&rng.range(3..9)));
+        }
+    }
+
+    #[test]
+    fn accepts_agreeing_variants() {
+        let variants: [Variant<&str, usize>; 2] = [
+            ("chars", |s| Some(s.chars().count())),
+            ("bytes", |s| Some(s.len())),
+        ];
+        assert_agree(&variants, "abc", 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn rejects_disagreeing_variants() {
+        let variants: [Variant<&str, usize>; 2] = [("len", |s| Some(s.len())), ("none", |_| None)];
+        assert_agree(&variants, "abc", 1);
+    }
+}